@@ -0,0 +1,16 @@
+//! bytes -> Varsig -> bytes: anything the decoder accepts must re-encode to
+//! bytes that decode to the same varsig and re-encode unchanged
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use varsig::Varsig;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(vs) = Varsig::try_from(data) {
+        let v: Vec<u8> = vs.clone().into();
+        let vs2 = Varsig::try_from(v.as_slice()).expect("re-encoded varsig decodes");
+        assert_eq!(vs, vs2);
+        let v2: Vec<u8> = vs2.into();
+        assert_eq!(v, v2);
+    }
+});
@@ -0,0 +1,15 @@
+//! Varsig -> bytes -> Varsig: every varsig must survive encoding, through
+//! both the owned and the buffer encoders
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use varsig::Varsig;
+
+fuzz_target!(|vs: Varsig| {
+    let v: Vec<u8> = vs.clone().into();
+    assert_eq!(v.len(), vs.encoded_len());
+    let mut buf = vec![0; v.len()];
+    assert_eq!(vs.encode_into(&mut buf).unwrap(), v.len());
+    assert_eq!(v, buf);
+    assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+});
@@ -0,0 +1,57 @@
+//! Metadata about the signing algorithms this crate knows, so tools and the
+//! header checks agree on the signature lengths and hashes.
+use multicodec::Codec;
+
+/// What is known about the signing algorithm of a key codec
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlgInfo {
+    /// the human name of the algorithm, e.g. "EdDSA"
+    pub name: &'static str,
+    /// the key type the algorithm signs with, e.g. "Ed25519"
+    pub key_type: &'static str,
+    /// the signature length in bytes, if it is fixed by the algorithm
+    pub signature_len: Option<usize>,
+    /// the hash codec the payload is usually hashed with, if the header
+    /// records one
+    pub default_hash: Option<Codec>,
+}
+
+/// get the metadata of the signing algorithm for a key codec
+pub fn info(codec: Codec) -> Option<AlgInfo> {
+    let (name, key_type, signature_len, default_hash) = match codec {
+        Codec::Ed25519Pub => ("EdDSA", "Ed25519", Some(64), None),
+        Codec::Ed448Pub => ("EdDSA", "Ed448", Some(114), None),
+        // r || s, optionally followed by a recovery id
+        Codec::Secp256K1Pub => ("ES256K", "secp256k1", None, Some(Codec::Sha2256)),
+        // r || s
+        Codec::P256Pub => ("ES256", "P-256", Some(64), Some(Codec::Sha2256)),
+        // as long as the key modulus
+        Codec::RsaPub => ("RSA", "RSA", None, Some(Codec::Sha2256)),
+        Codec::Sr25519Pub => ("sr25519", "sr25519", Some(64), None),
+        _ => return None,
+    };
+    Some(AlgInfo {
+        name,
+        key_type,
+        signature_len,
+        default_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info() {
+        let info = info(Codec::Ed25519Pub).unwrap();
+        assert_eq!(info.name, "EdDSA");
+        assert_eq!(info.signature_len, Some(64));
+        assert_eq!(info.default_hash, None);
+        assert_eq!(
+            super::info(Codec::P256Pub).unwrap().default_hash,
+            Some(Codec::Sha2256)
+        );
+        assert!(super::info(Codec::DagCbor).is_none());
+    }
+}
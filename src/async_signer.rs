@@ -0,0 +1,99 @@
+use crate::{Builder, Error, Varsig};
+use ssh_key::Signature;
+use std::future::Future;
+
+/// Produces varsigs with a key that lives somewhere it takes a round trip to
+/// reach, e.g. an ssh-agent or a cloud KMS, without blocking the caller
+pub trait AsyncVarsigSigner {
+    /// sign msg, producing a varsig
+    fn sign(&self, msg: &[u8]) -> impl Future<Output = Result<Varsig, Error>> + Send;
+}
+
+/// Adapts a backend that returns raw signature bytes, as most KMS APIs do.
+/// The builder supplies the rest of the varsig: key codec, payload encoding
+/// and attributes.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner<F> {
+    builder: Builder,
+    sign: F,
+}
+
+impl<F> RemoteSigner<F> {
+    /// wrap a backend's sign function, describing its signatures with the
+    /// builder
+    pub fn new(builder: Builder, sign: F) -> Self {
+        Self { builder, sign }
+    }
+}
+
+impl<F, Fut> AsyncVarsigSigner for RemoteSigner<F>
+where
+    F: Fn(Vec<u8>) -> Fut + Sync,
+    Fut: Future<Output = Result<Vec<u8>, Error>> + Send,
+{
+    fn sign(&self, msg: &[u8]) -> impl Future<Output = Result<Varsig, Error>> + Send {
+        let signature = (self.sign)(msg.to_vec());
+        let builder = self.builder.clone();
+        async move {
+            let signature = signature.await?;
+            builder.with_signature_bytes(&signature).try_build()
+        }
+    }
+}
+
+/// Adapts a backend that returns SSH signatures, such as an ssh-agent client
+#[derive(Clone, Debug)]
+pub struct SshAgentSigner<F> {
+    sign: F,
+}
+
+impl<F> SshAgentSigner<F> {
+    /// wrap an ssh-agent client's sign function
+    pub fn new(sign: F) -> Self {
+        Self { sign }
+    }
+}
+
+impl<F, Fut> AsyncVarsigSigner for SshAgentSigner<F>
+where
+    F: Fn(Vec<u8>) -> Fut + Sync,
+    Fut: Future<Output = Result<Signature, Error>> + Send,
+{
+    fn sign(&self, msg: &[u8]) -> impl Future<Output = Result<Varsig, Error>> + Send {
+        let signature = (self.sign)(msg.to_vec());
+        async move { Ok(Builder::new_from_ssh_signature(&signature.await?)?.build()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::ready;
+    use multicodec::Codec;
+    use ssh_key::Algorithm;
+
+    #[test]
+    fn test_remote_signer() {
+        let signer = RemoteSigner::new(
+            Builder::newv2(Codec::Ed25519Pub).with_msg_encoding(Codec::Raw),
+            |msg: Vec<u8>| async move { Ok::<_, Error>(msg.repeat(64 / msg.len())) },
+        );
+        let vs = ready(signer.sign(b"zig!")).unwrap();
+        assert!(matches!(vs, Varsig::EdDSA { .. }));
+        assert_eq!(vs.as_ref(), b"zig!".repeat(16).as_slice());
+
+        // signatures the builder's algorithm doesn't allow are rejected
+        let vs = ready(signer.sign(b"zig")).unwrap_err();
+        assert!(matches!(vs, Error::InvalidSignatureLength { .. }));
+    }
+
+    #[test]
+    fn test_ssh_agent_signer() {
+        let signer = SshAgentSigner::new(|_: Vec<u8>| async {
+            Signature::new(Algorithm::Ed25519, [7u8; 64].to_vec()).map_err(Error::from)
+        });
+        let vs = ready(signer.sign(b"for great justice")).unwrap();
+        assert!(matches!(vs, Varsig::EdDSA { .. }));
+        assert_eq!(vs.as_ref(), [7u8; 64].as_slice());
+    }
+}
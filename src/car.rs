@@ -0,0 +1,253 @@
+//! Verifying the varsigs in a CAR (content addressable archive) file.
+//!
+//! A CARv1 file is a varuint length prefixed DAG-CBOR header naming the root
+//! CIDs followed by varuint length prefixed sections, each the CID of a block
+//! and the block itself. Varsig-bearing blocks are signed envelopes, the
+//! layout UCAN 1.0 uses: a DAG-CBOR list of the signature and the payload it
+//! signs, a map whose `h` entry is the varsig header. The signature is over
+//! the payload map encoded as canonical DAG-CBOR.
+use crate::{dag_cbor, Error, Varsig, VarsigHeader};
+use ipld_core::{cid::Cid, ipld::Ipld};
+
+/// the dag-cbor multicodec, the only block codec envelopes are looked for in
+const DAG_CBOR: u64 = 0x71;
+
+/// Reads the blocks of a CARv1 file, yielding each block's CID and data
+#[derive(Clone, Debug)]
+pub struct CarReader<'a> {
+    roots: Vec<Cid>,
+    rest: &'a [u8],
+}
+
+impl<'a> CarReader<'a> {
+    /// read the CAR header, leaving the reader at the first block
+    pub fn new(car: &'a [u8]) -> Result<Self, Error> {
+        let (header, rest) = section(car)?;
+        let header: Ipld = serde_ipld_dagcbor::from_slice(header)
+            .map_err(|e| Error::InvalidCar(format!("invalid header: {}", e)))?;
+        let map = match header {
+            Ipld::Map(map) => map,
+            _ => return Err(Error::InvalidCar("the header is not a map".to_string())),
+        };
+        match map.get("version") {
+            Some(Ipld::Integer(1)) => {}
+            _ => return Err(Error::InvalidCar("only CARv1 is supported".to_string())),
+        }
+        let roots = match map.get("roots") {
+            Some(Ipld::List(roots)) => roots
+                .iter()
+                .map(|root| match root {
+                    Ipld::Link(cid) => Ok(*cid),
+                    _ => Err(Error::InvalidCar("a root is not a CID".to_string())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(Error::InvalidCar("the header has no roots".to_string())),
+        };
+        Ok(Self { roots, rest })
+    }
+
+    /// the root CIDs named in the header
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+}
+
+impl<'a> Iterator for CarReader<'a> {
+    type Item = Result<(Cid, &'a [u8]), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let block = section(self.rest).and_then(|(mut block, rest)| {
+            self.rest = rest;
+            let cid = Cid::read_bytes(&mut block)
+                .map_err(|e| Error::InvalidCar(format!("invalid block CID: {}", e)))?;
+            Ok((cid, block))
+        });
+        // nothing after a malformed section can be trusted
+        if block.is_err() {
+            self.rest = &[];
+        }
+        Some(block)
+    }
+}
+
+/// split a varuint length prefixed section off the front of the input
+fn section(input: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    let (len, rest) = unsigned_varint::decode::usize(input)
+        .map_err(|e| Error::InvalidCar(format!("invalid section length: {}", e)))?;
+    if rest.len() < len {
+        return Err(Error::InvalidCar("truncated section".to_string()));
+    }
+    Ok(rest.split_at(len))
+}
+
+/// get the varsig and the payload it signs from a signed envelope, or None
+/// if the block isn't one
+pub fn envelope(block: &Ipld) -> Result<Option<(Varsig, Vec<u8>)>, Error> {
+    let (signature, payload) = match block {
+        Ipld::List(list) => match list.as_slice() {
+            [Ipld::Bytes(signature), payload @ Ipld::Map(_)] => (signature, payload),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let header = match payload {
+        Ipld::Map(map) => match map.get("h") {
+            Some(Ipld::Bytes(header)) => header,
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let vs = VarsigHeader::try_from(header.as_slice())?.attach(signature)?;
+    Ok(Some((vs, dag_cbor::encode(payload)?)))
+}
+
+/// The outcome of checking one signed envelope in a CAR file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checked {
+    /// the CID of the block holding the envelope
+    pub cid: Cid,
+    /// the envelope's varsig
+    pub varsig: Varsig,
+    /// the index of the public key the varsig verified with, or None if it
+    /// verified with none of them
+    pub key: Option<usize>,
+}
+
+/// verify every signed envelope in a CAR file against the public keys,
+/// in block order. blocks that aren't DAG-CBOR or aren't envelopes are
+/// skipped; a malformed file or envelope is an error. see
+/// [`Varsig::verify`] for the supported algorithms and key formats.
+pub fn verify_car(car: &[u8], public_keys: &[&[u8]]) -> Result<Vec<Checked>, Error> {
+    let mut checked = Vec::default();
+    for block in CarReader::new(car)? {
+        let (cid, data) = block?;
+        if cid.codec() != DAG_CBOR {
+            continue;
+        }
+        let ipld: Ipld = serde_ipld_dagcbor::from_slice(data)
+            .map_err(|e| Error::InvalidCar(format!("invalid block {}: {}", cid, e)))?;
+        if let Some((varsig, payload)) = envelope(&ipld)? {
+            let key = public_keys
+                .iter()
+                .position(|pk| varsig.verify(pk, &payload).is_ok());
+            checked.push(Checked { cid, varsig, key });
+        }
+    }
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use ipld_core::cid::multihash::Multihash;
+    use multicodec::Codec;
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+
+    fn cid(codec: u64, data: &[u8]) -> Cid {
+        Cid::new_v1(codec, Multihash::wrap(0x12, &Sha256::digest(data)).unwrap())
+    }
+
+    fn push_section(car: &mut Vec<u8>, parts: &[&[u8]]) {
+        let len: usize = parts.iter().map(|p| p.len()).sum();
+        let mut buf = unsigned_varint::encode::usize_buffer();
+        car.extend_from_slice(unsigned_varint::encode::usize(len, &mut buf));
+        for part in parts {
+            car.extend_from_slice(part);
+        }
+    }
+
+    fn car(roots: &[Cid], blocks: &[(Cid, Vec<u8>)]) -> Vec<u8> {
+        let header = Ipld::Map(BTreeMap::from([
+            (
+                "roots".to_string(),
+                Ipld::List(roots.iter().map(|c| Ipld::Link(*c)).collect()),
+            ),
+            ("version".to_string(), Ipld::Integer(1)),
+        ]));
+        let mut car = Vec::default();
+        push_section(&mut car, &[&dag_cbor::encode(&header).unwrap()]);
+        for (cid, data) in blocks {
+            push_section(&mut car, &[&cid.to_bytes(), data]);
+        }
+        car
+    }
+
+    #[test]
+    fn test_car_reader() {
+        let raw = cid(0x55, b"for great justice");
+        let bytes = car(&[raw], &[(raw, b"for great justice".to_vec())]);
+        let reader = CarReader::new(&bytes).unwrap();
+        assert_eq!(reader.roots(), &[raw]);
+        let blocks: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(blocks, vec![(raw, b"for great justice".as_slice())]);
+
+        // a truncated block is an error and ends the walk
+        let mut reader = CarReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(reader.next(), Some(Err(Error::InvalidCar(_)))));
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "ed25519-dalek")]
+    #[test]
+    fn test_verify_car() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let template = Builder::newv2(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::DagCbor)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let header: Vec<u8> = VarsigHeader::from(&template).into();
+
+        // a ucan-style envelope around a payload signed as dag-cbor
+        let envelope = |key: &SigningKey, tag: &str| {
+            let payload = Ipld::Map(BTreeMap::from([
+                ("h".to_string(), Ipld::Bytes(header.clone())),
+                (
+                    tag.to_string(),
+                    Ipld::Map(BTreeMap::from([(
+                        "cmd".to_string(),
+                        Ipld::String("/zig".to_string()),
+                    )])),
+                ),
+            ]));
+            let sig = key.sign(&dag_cbor::encode(&payload).unwrap());
+            let block = Ipld::List(vec![Ipld::Bytes(sig.to_bytes().to_vec()), payload]);
+            let data = dag_cbor::encode(&block).unwrap();
+            (cid(DAG_CBOR, &data), data)
+        };
+        let signed = envelope(&sk, "ucan/dlg@1.0.0-rc.1");
+        let unknown = envelope(&other, "ucan/inv@1.0.0-rc.1");
+        let plain = dag_cbor::encode(&Ipld::String("no signature".to_string())).unwrap();
+        let plain = (cid(DAG_CBOR, &plain), plain);
+        let raw = (cid(0x55, b"raw"), b"raw".to_vec());
+        let bytes = car(&[signed.0], &[signed.clone(), plain, raw, unknown.clone()]);
+
+        let pk = sk.verifying_key().to_bytes();
+        let checked = verify_car(&bytes, &[&[1u8; 32], &pk]).unwrap();
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[0].cid, signed.0);
+        assert!(matches!(checked[0].varsig, Varsig::EdDSA { .. }));
+        assert_eq!(checked[0].key, Some(1));
+        assert_eq!(checked[1].cid, unknown.0);
+        assert_eq!(checked[1].key, None);
+    }
+
+    #[test]
+    fn test_invalid_car() {
+        assert!(matches!(
+            CarReader::new(&[0x05, 0xa0]),
+            Err(Error::InvalidCar(_))
+        ));
+        let mut bytes = Vec::default();
+        let header = Ipld::Map(BTreeMap::from([("version".to_string(), Ipld::Integer(2))]));
+        push_section(&mut bytes, &[&dag_cbor::encode(&header).unwrap()]);
+        assert!(matches!(CarReader::new(&bytes), Err(Error::InvalidCar(_))));
+    }
+}
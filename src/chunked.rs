@@ -0,0 +1,127 @@
+use crate::{EncodedVarsig, Error};
+use std::fmt;
+
+/// Displays an [`EncodedVarsig`] in fixed width groups, optionally wrapped
+/// onto multiple lines and followed by a short checksum, so that long encoded
+/// varsigs can be read aloud, printed and compared by a human.
+///
+/// With a group width of 4, 4 groups per line and a checksum it looks like:
+///
+/// ```text
+/// u7QE AQAA AAAA AAAA
+/// AAAA AAAA #1a2b
+/// ```
+#[derive(Clone, Debug)]
+pub struct Chunked<'a> {
+    encoded: &'a EncodedVarsig,
+    width: usize,
+    per_line: Option<usize>,
+    checksum: bool,
+}
+
+impl<'a> Chunked<'a> {
+    /// display the encoded varsig in groups of width characters
+    pub fn new(encoded: &'a EncodedVarsig, width: usize) -> Self {
+        Self {
+            encoded,
+            width: width.max(1),
+            per_line: None,
+            checksum: false,
+        }
+    }
+
+    /// start a new line after every n groups
+    pub fn with_groups_per_line(mut self, n: usize) -> Self {
+        self.per_line = Some(n.max(1));
+        self
+    }
+
+    /// append a checksum of the encoded string
+    pub fn with_checksum(mut self, checksum: bool) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// parse chunked output back into an [`EncodedVarsig`], ignoring
+    /// whitespace and verifying the checksum if there is one
+    pub fn parse(s: &str) -> Result<EncodedVarsig, Error> {
+        let (body, sum) = match s.rsplit_once('#') {
+            Some((body, sum)) => (body, Some(sum.trim())),
+            None => (s, None),
+        };
+        let encoded: String = body.split_whitespace().collect();
+        if let Some(sum) = sum {
+            if sum != format!("{:04x}", checksum(&encoded)) {
+                return Err(Error::ChecksumMismatch);
+            }
+        }
+        Ok(EncodedVarsig::try_from(encoded.as_str())?)
+    }
+}
+
+impl fmt::Display for Chunked<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let encoded = self.encoded.to_string();
+        let groups = encoded.as_bytes().chunks(self.width);
+        for (i, group) in groups.enumerate() {
+            if i > 0 {
+                match self.per_line {
+                    Some(n) if i % n == 0 => writeln!(f)?,
+                    _ => write!(f, " ")?,
+                }
+            }
+            // the encoded string is always ascii so groups are valid utf-8
+            write!(f, "{}", String::from_utf8_lossy(group))?;
+        }
+        if self.checksum {
+            write!(f, " #{:04x}", checksum(&encoded))?;
+        }
+        Ok(())
+    }
+}
+
+/// fletcher-16 over the encoded string
+fn checksum(s: &str) -> u16 {
+    let (a, b) = s.bytes().fold((0u16, 0u16), |(a, b), c| {
+        let a = (a + c as u16) % 255;
+        (a, (b + a) % 255)
+    });
+    (b << 8) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use multibase::Base;
+    use multicodec::Codec;
+
+    #[test]
+    fn test_chunked() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .with_encoding(Base::Base58Btc)
+            .build_encoded();
+        let s = Chunked::new(&vs, 4)
+            .with_groups_per_line(8)
+            .with_checksum(true)
+            .to_string();
+        assert!(s.lines().count() > 1);
+        assert!(s.lines().all(|l| l.split(' ').all(|g| g.len() <= 5)));
+        assert_eq!(vs, Chunked::parse(&s).unwrap());
+
+        let plain = Chunked::new(&vs, 8).to_string();
+        assert_eq!(vs, Chunked::parse(&plain).unwrap());
+    }
+
+    #[test]
+    fn test_bad_checksum() {
+        let _lock = crate::registry::lock_encodings();
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build_encoded();
+        let s = Chunked::new(&vs, 4).with_checksum(true).to_string();
+        let (body, _) = s.rsplit_once('#').unwrap();
+        assert!(Chunked::parse(&format!("{}#0000", body)).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! COSE_Sign1 (RFC 9052) messages to and from varsigs. A COSE signature is
+//! over the CBOR encoded Sig_structure built from the protected header and
+//! the payload, see [`coset::CoseSign1::tbs_data`], so varsigs taken from COSE
+//! messages record CBOR as their payload encoding. The varsig has no room for
+//! the protected header, so it travels alongside the varsig in both
+//! directions, byte for byte as it was signed.
+use crate::{Builder, Error, RsaPadding, Varsig};
+use coset::{
+    iana, CoseSign1, CoseSign1Builder, HeaderBuilder, ProtectedHeader, RegisteredLabelWithPrivate,
+};
+use multicodec::Codec;
+
+/// the COSE algorithm of a varsig. COSE signatures are raw, so DER ECDSA
+/// signatures and secp256k1 signatures carrying a recovery id have none.
+pub fn algorithm(vs: &Varsig) -> Result<iana::Algorithm, Error> {
+    let alg = match vs {
+        Varsig::EdDSA { .. } | Varsig::Ed448 { .. } => iana::Algorithm::EdDSA,
+        Varsig::Es256 {
+            hash: Codec::Sha2256,
+            der: false,
+            ..
+        } => iana::Algorithm::ES256,
+        Varsig::Es256K {
+            hash: Codec::Sha2256,
+            der: false,
+            signature,
+            ..
+        } if signature.len() == 64 => iana::Algorithm::ES256K,
+        Varsig::Rsa { hash, padding, .. } => match (hash, padding) {
+            (Codec::Sha2256, RsaPadding::Pkcs1v15) => iana::Algorithm::RS256,
+            (Codec::Sha2384, RsaPadding::Pkcs1v15) => iana::Algorithm::RS384,
+            (Codec::Sha2512, RsaPadding::Pkcs1v15) => iana::Algorithm::RS512,
+            // cose fixes the salt length to the hash length
+            (Codec::Sha2256, RsaPadding::Pss { salt_len: 32 }) => iana::Algorithm::PS256,
+            (Codec::Sha2384, RsaPadding::Pss { salt_len: 48 }) => iana::Algorithm::PS384,
+            (Codec::Sha2512, RsaPadding::Pss { salt_len: 64 }) => iana::Algorithm::PS512,
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs))),
+        },
+        _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs))),
+    };
+    Ok(alg)
+}
+
+/// get the varsig from a COSE_Sign1 message, from the algorithm in its
+/// protected header and its signature. the protected header is returned
+/// as well since the signature covers it; verify the varsig over
+/// [`CoseSign1::tbs_data`] of the message.
+pub fn from_cose_sign1(msg: &CoseSign1) -> Result<(Varsig, ProtectedHeader), Error> {
+    let alg = match &msg.protected.header.alg {
+        Some(RegisteredLabelWithPrivate::Assigned(alg)) => *alg,
+        Some(alg) => return Err(Error::UnsupportedAlgorithm(format!("{:?}", alg))),
+        None => {
+            return Err(Error::InvalidCose(
+                "the protected header has no algorithm".to_string(),
+            ))
+        }
+    };
+    let signature = msg.signature.as_slice();
+    let builder = match alg {
+        // ed25519 and ed448 share the algorithm
+        iana::Algorithm::EdDSA if signature.len() == 114 => Builder::newv2(Codec::Ed448Pub),
+        iana::Algorithm::EdDSA => Builder::newv2(Codec::Ed25519Pub),
+        iana::Algorithm::ES256 => Builder::newv2(Codec::P256Pub).with_hash_codec(Codec::Sha2256),
+        iana::Algorithm::ES256K => {
+            Builder::newv2(Codec::Secp256K1Pub).with_hash_codec(Codec::Sha2256)
+        }
+        iana::Algorithm::RS256 => rsa(Codec::Sha2256, signature, RsaPadding::Pkcs1v15),
+        iana::Algorithm::RS384 => rsa(Codec::Sha2384, signature, RsaPadding::Pkcs1v15),
+        iana::Algorithm::RS512 => rsa(Codec::Sha2512, signature, RsaPadding::Pkcs1v15),
+        iana::Algorithm::PS256 => rsa(Codec::Sha2256, signature, RsaPadding::Pss { salt_len: 32 }),
+        iana::Algorithm::PS384 => rsa(Codec::Sha2384, signature, RsaPadding::Pss { salt_len: 48 }),
+        iana::Algorithm::PS512 => rsa(Codec::Sha2512, signature, RsaPadding::Pss { salt_len: 64 }),
+        _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", alg))),
+    };
+    let vs = builder
+        .with_msg_encoding(Codec::Cbor)
+        .with_signature_bytes(signature)
+        .try_build()?;
+    Ok((vs, msg.protected.clone()))
+}
+
+fn rsa(hash: Codec, signature: &[u8], padding: RsaPadding) -> Builder {
+    Builder::newv2(Codec::RsaPub)
+        .with_rsa_params(hash, signature.len())
+        .with_rsa_padding(padding)
+}
+
+/// render a varsig as a COSE_Sign1 message with the protected header it was
+/// signed over and the key id, if any, in the unprotected header. the
+/// protected header is kept as is, including its original bytes, and must
+/// name the varsig's algorithm. the payload is left out for detached
+/// signatures.
+pub fn to_cose_sign1(
+    vs: &Varsig,
+    protected: ProtectedHeader,
+    payload: Option<Vec<u8>>,
+    key_id: Option<Vec<u8>>,
+) -> Result<CoseSign1, Error> {
+    let alg = algorithm(vs)?;
+    match &protected.header.alg {
+        Some(RegisteredLabelWithPrivate::Assigned(a)) if *a == alg => {}
+        Some(a) => {
+            return Err(Error::InvalidCose(format!(
+                "the protected header algorithm {:?} is not the varsig's {:?}",
+                a, alg
+            )))
+        }
+        None => {
+            return Err(Error::InvalidCose(
+                "the protected header has no algorithm".to_string(),
+            ))
+        }
+    }
+    let mut unprotected = HeaderBuilder::new();
+    if let Some(key_id) = key_id {
+        unprotected = unprotected.key_id(key_id);
+    }
+    let mut builder = CoseSign1Builder::new()
+        .unprotected(unprotected.build())
+        .signature(vs.signature());
+    if let Some(payload) = payload {
+        builder = builder.payload(payload);
+    }
+    let mut msg = builder.build();
+    msg.protected = protected;
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::CborSerializable;
+
+    fn protected(alg: iana::Algorithm) -> ProtectedHeader {
+        ProtectedHeader {
+            original_data: None,
+            header: HeaderBuilder::new().algorithm(alg).build(),
+        }
+    }
+
+    #[test]
+    fn test_cose_roundtrip() {
+        let varsigs = [
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Cbor)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::P256Pub)
+                .with_msg_encoding(Codec::Cbor)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::RsaPub)
+                .with_msg_encoding(Codec::Cbor)
+                .with_rsa_params(Codec::Sha2384, 256)
+                .with_rsa_padding(RsaPadding::Pss { salt_len: 48 })
+                .with_signature_bytes([3u8; 256].as_slice())
+                .build(),
+        ];
+        for vs in varsigs {
+            let header = protected(algorithm(&vs).unwrap());
+            let msg = to_cose_sign1(
+                &vs,
+                header.clone(),
+                Some(b"for great justice".to_vec()),
+                Some(b"k1".to_vec()),
+            )
+            .unwrap();
+            assert_eq!(msg.unprotected.key_id, b"k1".to_vec());
+            let bytes = msg.to_vec().unwrap();
+            let msg = CoseSign1::from_slice(&bytes).unwrap();
+            assert_eq!(msg.payload, Some(b"for great justice".to_vec()));
+            let (vs2, protected) = from_cose_sign1(&msg).unwrap();
+            assert_eq!(vs2, vs);
+            assert_eq!(protected.header, header.header);
+        }
+    }
+
+    #[test]
+    fn test_cose_sign_verify() {
+        use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let payload = b"for great justice".to_vec();
+        // a protected header with more than the algorithm in it, which
+        // rebuilding from the varsig alone would lose
+        let header = ProtectedHeader {
+            original_data: None,
+            header: HeaderBuilder::new()
+                .algorithm(iana::Algorithm::EdDSA)
+                .content_type("text/plain".to_string())
+                .build(),
+        };
+        let unsigned = CoseSign1Builder::new()
+            .protected(header.header.clone())
+            .payload(payload.clone())
+            .build();
+        let sig = sk.sign(&unsigned.tbs_data(b""));
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::Cbor)
+            .with_signature_bytes(&sig.to_bytes())
+            .build();
+
+        let msg = to_cose_sign1(&vs, header, Some(payload), None).unwrap();
+        let bytes = msg.to_vec().unwrap();
+
+        // a verifier checks the signature over the message's Sig_structure
+        let msg = CoseSign1::from_slice(&bytes).unwrap();
+        let (vs2, protected) = from_cose_sign1(&msg).unwrap();
+        assert_eq!(vs2, vs);
+        assert!(protected.original_data.is_some());
+        assert_eq!(
+            protected.header.content_type,
+            unsigned.protected.header.content_type
+        );
+        let sig = Signature::from_slice(vs2.signature_bytes()).unwrap();
+        assert!(sk.verifying_key().verify(&msg.tbs_data(b""), &sig).is_ok());
+
+        // and the original protected bytes are carried back out unchanged
+        let again = to_cose_sign1(&vs2, protected, msg.payload.clone(), None).unwrap();
+        assert_eq!(again.tbs_data(b""), msg.tbs_data(b""));
+    }
+
+    #[test]
+    fn test_cose_unsupported() {
+        // the recovery id has no place in a cose signature
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build();
+        assert!(matches!(
+            to_cose_sign1(&vs, protected(iana::Algorithm::ES256K), None, None),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+
+        // the protected header must name the varsig's algorithm
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            to_cose_sign1(&vs, protected(iana::Algorithm::ES256), None, None),
+            Err(Error::InvalidCose(_))
+        ));
+        assert!(matches!(
+            to_cose_sign1(&vs, ProtectedHeader::default(), None, None),
+            Err(Error::InvalidCose(_))
+        ));
+
+        let msg = CoseSign1Builder::new().signature(vec![1u8; 64]).build();
+        assert!(matches!(from_cose_sign1(&msg), Err(Error::InvalidCose(_))));
+    }
+}
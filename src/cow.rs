@@ -0,0 +1,1045 @@
+use crate::{
+    alg, registry,
+    varsig_ref::Attributes,
+    vs::{
+        BIP340, DER, ECDSA_FLAGS, IDENTITY, LOW_S, MAX_DER_LEN, MAX_ED25519_CONTEXT_LEN, RFC6979,
+        SHA2_512, SIGIL,
+    },
+    Error, RsaPadding, Varsig, VarsigRef, VarsigVersion,
+};
+use multicodec::Codec;
+use multitrait::TryDecodeFrom;
+use multiutil::Varuint;
+use std::{borrow::Cow, io};
+
+/// A varsig whose signature data is either borrowed from the buffer it was
+/// decoded from or owned. This is the common representation that both the
+/// owned [`Varsig`] and the decoder go through.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CowVarsig<'a> {
+    /// version of the varsig header
+    pub version: VarsigVersion,
+    /// the signing codec value, which may be in the private use range
+    pub code: u64,
+    /// msg encoding codec
+    pub msg_encoding: Option<Codec>,
+    /// further payload encoding segments after the msg encoding, e.g. the
+    /// encoding of the message an EIP-191 payload wraps. only v1 headers
+    /// carry them.
+    pub encoding_segments: Vec<Codec>,
+    /// signature-specific attributes
+    pub attributes: Vec<u64>,
+    /// the signature-specific data
+    pub signature: Cow<'a, [u8]>,
+}
+
+/// the most signature-specific attributes accepted by default; enough for a
+/// hash codec and a 255 byte signing context
+pub const DEFAULT_MAX_ATTRIBUTES: usize = 512;
+
+/// the longest signature accepted by default; enough for a 16384 bit RSA key
+pub const DEFAULT_MAX_SIGNATURE_LEN: usize = 2048;
+
+/// Bounds on what the decoder accepts, so a hostile header declaring a huge
+/// attribute count or signature length is rejected before anything is
+/// allocated for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DecodeLimits {
+    /// the most signature-specific attributes
+    pub max_attributes: usize,
+    /// the longest signature data in bytes
+    pub max_signature_len: usize,
+}
+
+impl DecodeLimits {
+    /// create limits with the given bounds
+    pub fn new(max_attributes: usize, max_signature_len: usize) -> Self {
+        Self {
+            max_attributes,
+            max_signature_len,
+        }
+    }
+
+    /// limits that accept anything the input can hold
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX, usize::MAX)
+    }
+
+    /// check a value against its limit
+    pub(crate) fn check(
+        field: &'static str,
+        offset: usize,
+        value: usize,
+        limit: usize,
+    ) -> Result<(), Error> {
+        if value > limit {
+            return Err(Error::LimitExceeded {
+                field,
+                offset,
+                value,
+                limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ATTRIBUTES, DEFAULT_MAX_SIGNATURE_LEN)
+    }
+}
+
+impl<'a> CowVarsig<'a> {
+    /// decode a varsig, rejecting it if it is over the limits
+    pub fn try_decode_with_limits(
+        bytes: &'a [u8],
+        limits: DecodeLimits,
+    ) -> Result<(Self, &'a [u8]), Error> {
+        let (vr, ptr) = VarsigRef::try_decode_with_limits(bytes, limits)?;
+        Ok((vr.into(), ptr))
+    }
+
+    /// get the signing codec, if it is not a private use codec
+    pub fn codec(&self) -> Option<Codec> {
+        Codec::try_from(self.code).ok()
+    }
+
+    /// check the header against the rules for its signing algorithm
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.version == VarsigVersion::V2 && !self.encoding_segments.is_empty() {
+            return Err(Error::InvalidEncodingInfo(
+                "v2 headers have a single payload encoding".to_string(),
+            ));
+        }
+        let (attributes, signature_len) = match registry::layout(self.code) {
+            Some(layout) => (Some(layout.attributes), layout.signature_len),
+            None => (None, None),
+        };
+        // bip-340 schnorr signatures may carry an aux-data attribute after the
+        // marker, and rsa-pss signatures a salt length after the key length,
+        // in v2 headers
+        let attributes = match (self.codec(), self.attributes.as_slice()) {
+            (Some(Codec::Secp256K1Pub), [BIP340, _]) if self.version == VarsigVersion::V2 => {
+                Some(2)
+            }
+            (Some(Codec::RsaPub), [_, _, _]) if self.version == VarsigVersion::V2 => Some(3),
+            // ecdsa signatures may record that they are low-s normalized or
+            // use deterministic nonces
+            (Some(Codec::Secp256K1Pub) | Some(Codec::P256Pub), [_, flags])
+                if self.version == VarsigVersion::V2 && ecdsa_flags(*flags) =>
+            {
+                Some(2)
+            }
+            // as do eip-712 signatures with the domain separator
+            (Some(Codec::Secp256K1Pub), [hash, rest @ ..])
+                if self.version == VarsigVersion::V2
+                    && *hash == Codec::Keccak256.code()
+                    && rest.len() == 33
+                    && signing_context(rest).is_some() =>
+            {
+                Some(34)
+            }
+            _ => attributes,
+        };
+        if self.codec() == Some(Codec::Sr25519Pub) && signing_context(&self.attributes).is_none() {
+            return Err(Error::InvalidAttributes(
+                "expected a signing context length followed by its bytes".to_string(),
+            ));
+        }
+        // ed25519ctx and ed25519ph carry the hash codec and a signing context
+        // in v2 headers; plain ed25519 has no attributes
+        let attributes = match (self.codec(), self.attributes.split_first()) {
+            (Some(Codec::Ed25519Pub), Some((hash, rest))) if self.version == VarsigVersion::V2 => {
+                if !matches!(*hash, IDENTITY | SHA2_512) || signing_context(rest).is_none() {
+                    return Err(Error::InvalidAttributes(
+                        "expected a hash codec and a signing context".to_string(),
+                    ));
+                }
+                // rfc 8032 limits the context to 255 bytes
+                if rest.len() > 1 + MAX_ED25519_CONTEXT_LEN {
+                    return Err(Error::InvalidAttributes(format!(
+                        "signing contexts are at most {} bytes",
+                        MAX_ED25519_CONTEXT_LEN
+                    )));
+                }
+                Some(self.attributes.len())
+            }
+            _ => attributes,
+        };
+        if let Some(expected) = attributes {
+            if self.attributes.len() != expected {
+                return Err(Error::InvalidAttributes(format!(
+                    "expected {} attributes, got {}",
+                    expected,
+                    self.attributes.len()
+                )));
+            }
+        }
+        // rsa signatures are as long as the key modulus
+        let signature_len = match (self.codec(), self.attributes.as_slice()) {
+            (Some(Codec::RsaPub), [_, key_len, ..]) => Some(*key_len as usize),
+            (Some(Codec::Sr25519Pub), _) => {
+                alg::info(Codec::Sr25519Pub).and_then(|a| a.signature_len)
+            }
+            // bip-340 schnorr signatures are always 64 bytes
+            (Some(Codec::Secp256K1Pub), [BIP340, ..]) => Some(64),
+            // der signatures vary in length
+            (Some(Codec::P256Pub), [_, flags]) if flags & DER != 0 => None,
+            _ => signature_len,
+        };
+        if let Some(expected) = signature_len {
+            if self.signature.len() != expected {
+                return Err(Error::InvalidSignatureLength {
+                    expected,
+                    actual: self.signature.len(),
+                });
+            }
+        }
+        // ecdsa signatures vary in length but are never longer than a der
+        // sequence, or r || s and a recovery id when not der encoded
+        let max_signature_len = match (self.codec(), self.attributes.as_slice()) {
+            (Some(Codec::Secp256K1Pub), [BIP340, ..]) => None,
+            (Some(Codec::Secp256K1Pub) | Some(Codec::P256Pub), [_, flags]) if flags & DER != 0 => {
+                Some(MAX_DER_LEN)
+            }
+            (Some(Codec::Secp256K1Pub), _) => Some(65),
+            _ => None,
+        };
+        if let Some(expected) = max_signature_len {
+            if self.signature.len() > expected {
+                return Err(Error::InvalidSignatureLength {
+                    expected,
+                    actual: self.signature.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// the number of bytes this varsig encodes to, not counting the sigil
+    pub fn encoded_len(&self) -> usize {
+        let len = varuint_len(u8::from(self.version) as u64)
+            + varuint_len(self.code)
+            + self
+                .attributes
+                .iter()
+                .map(|a| varuint_len(*a))
+                .sum::<usize>()
+            + self.signature.len();
+        if self.version == VarsigVersion::V2 {
+            len + varuint_len(self.msg_encoding.unwrap_or(Codec::Raw).code())
+                + varuint_len(self.attributes.len() as u64)
+                + varuint_len(self.signature.len() as u64)
+        } else {
+            len + self.msg_encoding.map_or(0, |c| varuint_len(c.code()))
+                + self
+                    .encoding_segments
+                    .iter()
+                    .map(|c| varuint_len(c.code()))
+                    .sum::<usize>()
+        }
+    }
+
+    /// encode into the start of buf, returning the number of bytes written,
+    /// which is [`CowVarsig::encoded_len`]
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(Error::BufferTooSmall {
+                needed: len,
+                available: buf.len(),
+            });
+        }
+        let mut pos = 0;
+        self.emit(|bytes| {
+            buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+            Ok::<_, Error>(())
+        })?;
+        Ok(pos)
+    }
+
+    /// encode into a writer, returning the number of bytes written
+    pub fn write_to<W: io::Write>(&self, mut w: W) -> io::Result<usize> {
+        let mut len = 0;
+        self.emit(|bytes| {
+            len += bytes.len();
+            w.write_all(bytes)
+        })?;
+        Ok(len)
+    }
+
+    /// hand the encoding to out a piece at a time
+    fn emit<E>(&self, mut out: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        let varuint = |v: u64, out: &mut dyn FnMut(&[u8]) -> Result<(), E>| {
+            let mut tmp = unsigned_varint::encode::u64_buffer();
+            out(unsigned_varint::encode::u64(v, &mut tmp))
+        };
+        // add in the version and the signing codec
+        varuint(u8::from(self.version) as u64, &mut out)?;
+        varuint(self.code, &mut out)?;
+        if self.version == VarsigVersion::V2 {
+            // add in the payload encoding, the number of signature specific
+            // attributes and the attributes
+            varuint(self.msg_encoding.unwrap_or(Codec::Raw).code(), &mut out)?;
+            varuint(self.attributes.len() as u64, &mut out)?;
+            for a in &self.attributes {
+                varuint(*a, &mut out)?;
+            }
+            // add in the signature data with its length
+            varuint(self.signature.len() as u64, &mut out)?;
+        } else {
+            // add in the signature specific attributes, the payload encoding
+            // and any further segments. without a known layout the decoder
+            // leaves the payload encoding in the signature data, so there is
+            // nothing to write for it.
+            for a in &self.attributes {
+                varuint(*a, &mut out)?;
+            }
+            if let Some(msg_encoding) = self.msg_encoding {
+                varuint(msg_encoding.code(), &mut out)?;
+            }
+            for c in &self.encoding_segments {
+                varuint(c.code(), &mut out)?;
+            }
+        }
+        // add the signature data
+        out(&self.signature)
+    }
+
+    /// true if the signature data is borrowed from the input
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.signature, Cow::Borrowed(_))
+    }
+
+    /// convert into an owned [`Varsig`], copying the signature if borrowed
+    pub fn into_owned(self) -> Varsig {
+        // algorithms that hash the payload carry the hash codec as the first
+        // attribute followed by any algorithm-specific values
+        let hash = self
+            .attributes
+            .first()
+            .and_then(|h| Codec::try_from(*h).ok());
+        // signing contexts follow the hash codec for ed25519ctx and ed25519ph
+        // and are the only attributes for sr25519
+        let context = match (self.codec(), self.attributes.split_first()) {
+            (Some(Codec::Ed25519Pub), Some((_, rest))) => signing_context(rest),
+            (Some(Codec::Sr25519Pub), _) => signing_context(&self.attributes),
+            // the eip-712 domain separator follows the keccak256 hash codec
+            (Some(Codec::Secp256K1Pub), Some((_, rest))) => signing_context(rest),
+            _ => None,
+        };
+        let eip191 = self.codec() == Some(Codec::Secp256K1Pub)
+            && hash == Some(Codec::Keccak256)
+            && self.attributes.len() == 1
+            && self.msg_encoding == Some(Codec::Eip191);
+        // only eip-191 varsigs have room for an encoding segment, the
+        // encoding of the message the payload wraps
+        if !(self.encoding_segments.is_empty() || eip191 && self.encoding_segments.len() == 1) {
+            return self.untyped();
+        }
+        match (self.codec(), hash, self.attributes.len()) {
+            (Some(Codec::Ed25519Pub), _, 0) => Varsig::EdDSA {
+                version: self.version,
+                msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                signature: self.signature.into_owned(),
+            },
+            (Some(Codec::Ed25519Pub), _, _)
+                if context.is_some() && self.attributes[0] == IDENTITY =>
+            {
+                Varsig::Ed25519ctx {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    context: context.unwrap_or_default(),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            (Some(Codec::Ed25519Pub), _, _)
+                if context.is_some() && self.attributes[0] == SHA2_512 =>
+            {
+                Varsig::Ed25519ph {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    context: context.unwrap_or_default(),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            (Some(Codec::Ed448Pub), _, _) => Varsig::Ed448 {
+                version: self.version,
+                msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                signature: self.signature.into_owned(),
+            },
+            (Some(Codec::Sr25519Pub), _, _) if context.is_some() => Varsig::Sr25519 {
+                version: self.version,
+                msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                context: context.unwrap_or_default(),
+                signature: self.signature.into_owned(),
+            },
+            (Some(Codec::Secp256K1Pub), _, 1 | 2) if self.attributes[0] == BIP340 => {
+                Varsig::Schnorr {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    aux: self.attributes.get(1).copied(),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            _ if eip191 => Varsig::Eip191 {
+                version: self.version,
+                payload_encoding: self.encoding_segments.first().copied(),
+                signature: self.signature.into_owned(),
+            },
+            (Some(Codec::Secp256K1Pub), Some(Codec::Keccak256), 34) if context.is_some() => {
+                Varsig::Eip712 {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    domain_separator: context.and_then(|c| c.try_into().ok()).unwrap_or_default(),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            (Some(Codec::Secp256K1Pub), Some(hash), 1 | 2)
+                if self.attributes.get(1).is_none_or(|f| ecdsa_flags(*f)) =>
+            {
+                Varsig::Es256K {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    hash,
+                    low_s: self.attributes.get(1).is_some_and(|f| f & LOW_S != 0),
+                    rfc6979: self.attributes.get(1).is_some_and(|f| f & RFC6979 != 0),
+                    der: self.attributes.get(1).is_some_and(|f| f & DER != 0),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            (Some(Codec::P256Pub), Some(hash), 1 | 2)
+                if self.attributes.get(1).is_none_or(|f| ecdsa_flags(*f)) =>
+            {
+                Varsig::Es256 {
+                    version: self.version,
+                    msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                    hash,
+                    low_s: self.attributes.get(1).is_some_and(|f| f & LOW_S != 0),
+                    rfc6979: self.attributes.get(1).is_some_and(|f| f & RFC6979 != 0),
+                    der: self.attributes.get(1).is_some_and(|f| f & DER != 0),
+                    signature: self.signature.into_owned(),
+                }
+            }
+            (Some(Codec::RsaPub), Some(hash), 2 | 3) => Varsig::Rsa {
+                version: self.version,
+                msg_encoding: self.msg_encoding.unwrap_or(Codec::Raw),
+                hash,
+                key_len: self.attributes[1] as usize,
+                padding: match self.attributes.get(2) {
+                    Some(salt_len) => RsaPadding::Pss {
+                        salt_len: *salt_len as usize,
+                    },
+                    None => RsaPadding::Pkcs1v15,
+                },
+                signature: self.signature.into_owned(),
+            },
+            _ => self.untyped(),
+        }
+    }
+
+    /// convert into a varsig that keeps the header as is, for signing codecs
+    /// this crate doesn't know and headers it can't type
+    fn untyped(self) -> Varsig {
+        match self.codec() {
+            Some(codec) => Varsig::Unknown {
+                version: self.version,
+                codec,
+                msg_encoding: self.msg_encoding,
+                encoding_segments: self.encoding_segments,
+                attributes: self.attributes,
+                signature: self.signature.into_owned(),
+            },
+            None => Varsig::PrivateUse {
+                version: self.version,
+                code: self.code,
+                msg_encoding: self.msg_encoding,
+                encoding_segments: self.encoding_segments,
+                attributes: self.attributes,
+                signature: self.signature.into_owned(),
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a Varsig> for CowVarsig<'a> {
+    fn from(vs: &'a Varsig) -> Self {
+        Self {
+            version: vs.version(),
+            code: vs.code(),
+            msg_encoding: vs.header_msg_encoding(),
+            encoding_segments: vs.encoding_segments(),
+            attributes: vs.attributes(),
+            signature: Cow::Borrowed(vs.signature_bytes()),
+        }
+    }
+}
+
+impl From<Varsig> for CowVarsig<'_> {
+    fn from(vs: Varsig) -> Self {
+        let CowVarsig {
+            version,
+            code,
+            msg_encoding,
+            encoding_segments,
+            attributes,
+            signature,
+        } = CowVarsig::from(&vs);
+        Self {
+            version,
+            code,
+            msg_encoding,
+            encoding_segments,
+            attributes,
+            signature: Cow::Owned(signature.into_owned()),
+        }
+    }
+}
+
+impl From<CowVarsig<'_>> for Varsig {
+    fn from(cv: CowVarsig<'_>) -> Self {
+        cv.into_owned()
+    }
+}
+
+impl From<CowVarsig<'_>> for Vec<u8> {
+    fn from(cv: CowVarsig<'_>) -> Self {
+        let mut v = vec![0; cv.encoded_len()];
+        // the buffer is exactly as long as the encoding
+        let _ = cv.encode_into(&mut v);
+        v
+    }
+}
+
+impl<'a> TryDecodeFrom<'a> for CowVarsig<'a> {
+    type Error = Error;
+
+    fn try_decode_from(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
+        let (vr, ptr) = VarsigRef::try_decode_from(bytes)?;
+        Ok((vr.into(), ptr))
+    }
+}
+
+/// parse a varsig in place, borrowing everything from the input
+pub(crate) fn decode(input: &[u8], limits: DecodeLimits) -> Result<(VarsigRef<'_>, &[u8]), Error> {
+    // skip the varsig sigil if the encoding is tagged. a valid version is
+    // never 0x34 so this is unambiguous
+    let bytes = match Codec::try_decode_from(input) {
+        Ok((sigil, ptr)) if sigil == SIGIL => ptr,
+        _ => input,
+    };
+    // decode the version
+    let (version, ptr) = read_version(input, bytes)?;
+    // decoded the signing codec, allowing private use values
+    let (code, p) = read::<Varuint<u64>>(input, ptr, "signing codec")?;
+    let code = code.to_inner();
+    if let Err(e) = Codec::try_from(code) {
+        if !registry::is_private_use(code) {
+            return Err(Error::InvalidField {
+                field: "signing codec",
+                offset: offset(input, ptr),
+                reason: e.to_string(),
+            });
+        }
+    }
+    let ptr = p;
+    // get the payload encoding if v2
+    let (msg_encoding, ptr) = match version {
+        VarsigVersion::V1 => (None, ptr),
+        VarsigVersion::V2 => {
+            // parse the encoding codec for the data that was signed
+            let (msg_encoding, ptr) = read::<Codec>(input, ptr, "payload encoding")?;
+            (Some(msg_encoding), ptr)
+        }
+    };
+    // get the attributes if v2
+    let (attributes, ptr) = match version {
+        VarsigVersion::V1 => (Attributes::new(0, &ptr[..0]), ptr),
+        VarsigVersion::V2 => {
+            // parse the number of attributes
+            let (len, p) = read::<Varuint<usize>>(input, ptr, "attribute count")?;
+            DecodeLimits::check(
+                "attribute count",
+                offset(input, ptr),
+                len.to_inner(),
+                limits.max_attributes,
+            )?;
+            let (attributes, ptr) = decode_attributes(input, len.to_inner(), p)?;
+            (Attributes::new(len.to_inner(), attributes), ptr)
+        }
+    };
+    // v1 varsigs can only be fully parsed if the layout of the signing codec
+    // is known
+    if version == VarsigVersion::V1 {
+        if let Some(layout) = registry::layout(code) {
+            DecodeLimits::check(
+                "attribute count",
+                offset(input, ptr),
+                layout.attributes,
+                limits.max_attributes,
+            )?;
+            let (attributes, ptr) = decode_attributes(input, layout.attributes, ptr)?;
+            let attributes = Attributes::new(layout.attributes, attributes);
+            let (msg_encoding, ptr) = read::<Codec>(input, ptr, "payload encoding")?;
+            // eip-191 payloads name the encoding of the message they wrap in
+            // a further segment, which older encoders left out
+            let (encoding_segment, ptr) = match Codec::try_decode_from(ptr) {
+                Ok((segment, p))
+                    if msg_encoding == Codec::Eip191 && more_than_signature(code, layout, ptr) =>
+                {
+                    (Some(segment), p)
+                }
+                _ => (None, ptr),
+            };
+            let len = layout.signature_len.unwrap_or(ptr.len());
+            DecodeLimits::check(
+                "signature",
+                offset(input, ptr),
+                len,
+                limits.max_signature_len,
+            )?;
+            let (signature, rest) = take(input, ptr, len, "signature")?;
+            return Ok((
+                VarsigRef {
+                    version,
+                    code,
+                    msg_encoding: Some(msg_encoding),
+                    encoding_segment,
+                    attributes,
+                    signature,
+                },
+                rest,
+            ));
+        }
+    }
+    let (signature, ptr) = match version {
+        // without a known layout the rest of the input is the signature
+        VarsigVersion::V1 => {
+            DecodeLimits::check(
+                "signature",
+                offset(input, ptr),
+                ptr.len(),
+                limits.max_signature_len,
+            )?;
+            (ptr, &ptr[ptr.len()..])
+        }
+        VarsigVersion::V2 => {
+            // parse the signature length and borrow the signature bytes
+            let (len, p) = read::<Varuint<usize>>(input, ptr, "signature length")?;
+            DecodeLimits::check(
+                "signature length",
+                offset(input, ptr),
+                len.to_inner(),
+                limits.max_signature_len,
+            )?;
+            take(input, p, len.to_inner(), "signature")?
+        }
+    };
+    // es256 signatures are the 64 byte r || s unless recorded as der
+    let mut flags = attributes;
+    let der = flags.nth(1).is_some_and(|f| f & DER != 0);
+    if matches!(Codec::try_from(code), Ok(Codec::P256Pub)) && !der && signature.len() != 64 {
+        return Err(Error::InvalidSignatureLength {
+            expected: 64,
+            actual: signature.len(),
+        });
+    }
+
+    Ok((
+        VarsigRef {
+            version,
+            code,
+            msg_encoding,
+            encoding_segment: None,
+            attributes,
+            signature,
+        },
+        ptr,
+    ))
+}
+
+/// decode the version. with the `unversioned-v1` feature a header that
+/// doesn't start with a valid version is read as an earlier v1 header, which
+/// has no version and starts with the signing codec. no signing codec's
+/// varuint is 1 or 2 so the two can't be confused.
+pub(crate) fn read_version<'a>(
+    input: &[u8],
+    bytes: &'a [u8],
+) -> Result<(VarsigVersion, &'a [u8]), Error> {
+    let result = read::<Varuint<u8>>(input, bytes, "version")
+        .and_then(|(version, ptr)| Ok((VarsigVersion::try_from(version.to_inner())?, ptr)));
+    #[cfg(feature = "unversioned-v1")]
+    if matches!(
+        result,
+        Err(Error::InvalidVersion(_) | Error::InvalidField { .. })
+    ) {
+        return Ok((VarsigVersion::V1, bytes));
+    }
+    result
+}
+
+/// the offset of ptr, a tail of input, from the start of input
+pub(crate) fn offset(input: &[u8], ptr: &[u8]) -> usize {
+    input.len() - ptr.len()
+}
+
+/// decode one field of the header, reporting the field and where it starts
+/// if it can't be decoded
+pub(crate) fn read<'a, T>(
+    input: &[u8],
+    ptr: &'a [u8],
+    field: &'static str,
+) -> Result<(T, &'a [u8]), Error>
+where
+    T: TryDecodeFrom<'a>,
+    T::Error: std::fmt::Display,
+{
+    T::try_decode_from(ptr).map_err(|e| {
+        let offset = offset(input, ptr);
+        // a varuint whose every byte has the continuation bit set ran off
+        // the end of the input
+        if ptr.iter().all(|b| b & 0x80 != 0) {
+            Error::Truncated { field, offset }
+        } else {
+            Error::InvalidField {
+                field,
+                offset,
+                reason: e.to_string(),
+            }
+        }
+    })
+}
+
+/// split off the next len bytes of the header
+fn take<'a>(
+    input: &[u8],
+    ptr: &'a [u8],
+    len: usize,
+    field: &'static str,
+) -> Result<(&'a [u8], &'a [u8]), Error> {
+    if ptr.len() < len {
+        return Err(Error::Truncated {
+            field,
+            offset: offset(input, ptr),
+        });
+    }
+    Ok(ptr.split_at(len))
+}
+
+/// the number of bytes needed to encode a value as a varuint
+pub(crate) const fn varuint_len(v: u64) -> usize {
+    let bits = (u64::BITS - v.leading_zeros()) as usize;
+    if bits == 0 {
+        1
+    } else {
+        bits.div_ceil(7)
+    }
+}
+
+/// true if the bytes left are too many to be just the signature, so they
+/// must start with another encoding segment. secp256k1 signatures are the 64
+/// byte r || s, optionally followed by a recovery id.
+fn more_than_signature(code: u64, layout: registry::Layout, bytes: &[u8]) -> bool {
+    match (layout.signature_len, Codec::try_from(code)) {
+        (Some(len), _) => bytes.len() > len,
+        (None, Ok(Codec::Secp256K1Pub)) => !matches!(bytes.len(), 64 | 65),
+        _ => false,
+    }
+}
+
+/// a non-empty set of known ES256K and ES256 flags
+fn ecdsa_flags(flags: u64) -> bool {
+    flags != 0 && flags & !ECDSA_FLAGS == 0
+}
+
+/// signing contexts are stored as their length followed by one attribute per
+/// byte
+pub(crate) fn context_attributes(context: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    [context.len() as u64]
+        .into_iter()
+        .chain(context.iter().map(|b| *b as u64))
+}
+
+/// the inverse of [`context_attributes`]
+fn signing_context(attributes: &[u64]) -> Option<Vec<u8>> {
+    match attributes.split_first() {
+        Some((len, context)) if *len == context.len() as u64 => {
+            context.iter().map(|b| u8::try_from(*b).ok()).collect()
+        }
+        _ => None,
+    }
+}
+
+/// check that the next len varuints are attributes and split them off
+pub(crate) fn decode_attributes<'a>(
+    input: &[u8],
+    len: usize,
+    bytes: &'a [u8],
+) -> Result<(&'a [u8], &'a [u8]), Error> {
+    let mut p = bytes;
+    for _ in 0..len {
+        // parse the varuint attribute
+        let (_, ptr) = read::<Varuint<u64>>(input, p, "attribute")?;
+        p = ptr;
+    }
+    Ok(bytes.split_at(bytes.len() - p.len()))
+}
+
+impl<'a> TryFrom<&'a [u8]> for CowVarsig<'a> {
+    type Error = Error;
+
+    fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
+        let (cv, _) = Self::try_decode_from(s)?;
+        Ok(cv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn test_borrowed_decode() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let cv = CowVarsig::try_from(v.as_slice()).unwrap();
+        assert!(cv.is_borrowed());
+        assert_eq!(vs, cv.into_owned());
+    }
+
+    #[test]
+    fn test_private_use_v1() {
+        let code = 0x3abcde;
+        registry::register_private(code, registry::Layout::new(1, Some(32))).unwrap();
+        let vs = Builder::newv1_private(code)
+            .with_msg_encoding(Codec::Raw)
+            .with_attributes(&[7].to_vec())
+            .with_signature_bytes([1u8; 32].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let (cv, rest) = CowVarsig::try_decode_from(v.as_slice()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(cv.codec(), None);
+        assert_eq!(vs, cv.into_owned());
+        registry::unregister_private(code);
+    }
+
+    #[test]
+    fn test_encode_into() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::DagCbor)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build();
+        let cv = CowVarsig::from(&vs);
+        let mut buf = [0u8; 128];
+        let len = cv.encode_into(&mut buf).unwrap();
+        assert_eq!(len, cv.encoded_len());
+        assert_eq!(vs, Varsig::try_from(&buf[..len]).unwrap());
+        assert!(matches!(
+            cv.encode_into(&mut buf[..len - 1]),
+            Err(Error::BufferTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // a v2 header declaring 2^32 attributes
+        let hostile = [0x02, 0xed, 0x01, 0x00, 0x80, 0x80, 0x80, 0x80, 0x10];
+        assert!(matches!(
+            CowVarsig::try_decode_from(hostile.as_slice()),
+            Err(Error::LimitExceeded {
+                field: "attribute count",
+                offset: 4,
+                ..
+            })
+        ));
+        // a v2 header declaring a 2^32 byte signature
+        let hostile = [0x02, 0xed, 0x01, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x10];
+        assert!(matches!(
+            CowVarsig::try_decode_from(hostile.as_slice()),
+            Err(Error::LimitExceeded {
+                field: "signature length",
+                offset: 5,
+                ..
+            })
+        ));
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let (cv, _) = CowVarsig::try_decode_with_limits(&v, DecodeLimits::new(1, 65)).unwrap();
+        assert_eq!(vs, cv.into_owned());
+        assert!(matches!(
+            CowVarsig::try_decode_with_limits(&v, DecodeLimits::new(0, 65)),
+            Err(Error::LimitExceeded {
+                value: 1,
+                limit: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            CowVarsig::try_decode_with_limits(&v, DecodeLimits::new(1, 64)),
+            Err(Error::LimitExceeded {
+                value: 65,
+                limit: 64,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_v1_unknown_layout_roundtrip() {
+        // bls12-381 g2 has no layout so everything after the codec is kept
+        // as the signature and must be written back unchanged
+        let v = [0x01, 0xeb, 0x01, 0x12, 0x71, 0xaa, 0xbb, 0xcc].to_vec();
+        let vs = Varsig::try_from(v.as_slice()).unwrap();
+        assert!(matches!(
+            vs,
+            Varsig::Unknown {
+                msg_encoding: None,
+                ..
+            }
+        ));
+        let v2: Vec<u8> = vs.clone().into();
+        assert_eq!(v, v2);
+        assert_eq!(vs.encoded_len(), v.len());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let vs = Builder::newv1(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::DagCbor)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let mut out = Vec::default();
+        let len = CowVarsig::from(&vs).write_to(&mut out).unwrap();
+        assert_eq!(len, out.len());
+        let v: Vec<u8> = vs.into();
+        assert_eq!(out, v);
+    }
+
+    #[test]
+    fn test_positional_errors() {
+        assert!(matches!(
+            CowVarsig::try_from([].as_slice()),
+            Err(Error::Truncated {
+                field: "version",
+                offset: 0
+            })
+        ));
+        // tagged, with the signing codec cut off mid-varuint
+        assert!(matches!(
+            CowVarsig::try_from([0x34, 0x02, 0xed].as_slice()),
+            Err(Error::Truncated {
+                field: "signing codec",
+                offset: 2
+            })
+        ));
+        // a v1 ed25519 signature shorter than 64 bytes
+        let v: Vec<u8> = Builder::newv1(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build()
+            .into();
+        assert!(matches!(
+            CowVarsig::try_from(&v[..40]),
+            Err(Error::Truncated {
+                field: "signature",
+                offset: 4
+            })
+        ));
+        // a v2 signature shorter than its declared length
+        let v: Vec<u8> = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build()
+            .into();
+        assert!(matches!(
+            CowVarsig::try_from(&v[..v.len() - 1]),
+            Err(Error::Truncated {
+                field: "signature",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unversioned_v1() {
+        // an earlier v1 header: the sigil, then the signing codec with no
+        // version
+        let mut v = [0x34, 0xed, 0x01, 0x55].to_vec();
+        v.extend_from_slice(&[1u8; 64]);
+        let result = Varsig::try_from(v.as_slice());
+        #[cfg(feature = "unversioned-v1")]
+        {
+            let vs = result.unwrap();
+            assert_eq!(
+                vs,
+                Builder::newv1(Codec::Ed25519Pub)
+                    .with_msg_encoding(Codec::Raw)
+                    .with_signature_bytes([1u8; 64].as_slice())
+                    .build()
+            );
+            // it is written back in the versioned layout
+            let v2: Vec<u8> = vs.into();
+            assert_eq!(v2[..4], [0x01, 0xed, 0x01, 0x55]);
+        }
+        #[cfg(not(feature = "unversioned-v1"))]
+        assert!(matches!(result, Err(Error::InvalidVersion(_))));
+    }
+
+    #[test]
+    fn test_unknown_roundtrip() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let cv = CowVarsig::from(&vs);
+        let v: Vec<u8> = cv.clone().into();
+        assert_eq!(cv, CowVarsig::try_from(v.as_slice()).unwrap());
+    }
+}
+
+#[cfg(kani)]
+mod verification {
+    use super::*;
+
+    const MAX_LEN: usize = 16;
+
+    #[kani::proof]
+    #[kani::unwind(17)]
+    fn decode_never_panics() {
+        let bytes: [u8; MAX_LEN] = kani::any();
+        let len: usize = kani::any();
+        kani::assume(len <= MAX_LEN);
+        let input = &bytes[..len];
+        // decode directly rather than through VarsigRef, which also records
+        // telemetry
+        if let Ok((vr, rest)) = decode(input, DecodeLimits::default()) {
+            let cv = CowVarsig::from(vr);
+            // everything decoded came from the input
+            assert!(rest.len() <= input.len());
+            assert!(cv.signature.len() <= input.len());
+            // allocation is bounded by the input size
+            assert!(cv.attributes.capacity() <= input.len());
+        }
+    }
+
+    #[kani::proof]
+    #[kani::unwind(17)]
+    fn decode_attributes_bounded() {
+        let bytes: [u8; MAX_LEN] = kani::any();
+        let count: usize = kani::any();
+        kani::assume(count <= MAX_LEN);
+        if let Ok((attributes, rest)) = decode_attributes(&bytes, count, &bytes) {
+            assert!(count <= attributes.len());
+            assert_eq!(attributes.len() + rest.len(), MAX_LEN);
+        }
+    }
+}
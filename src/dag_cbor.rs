@@ -0,0 +1,51 @@
+use crate::{Error, Varsig};
+use ipld_core::ipld::Ipld;
+
+/// encode IPLD data as canonical DAG-CBOR: shortest integer and length
+/// forms and map keys sorted by length and then bytewise
+pub fn encode(ipld: &Ipld) -> Result<Vec<u8>, Error> {
+    serde_ipld_dagcbor::to_vec(ipld).map_err(|e| Error::InvalidPayload(e.to_string()))
+}
+
+/// re-encode a DAG-CBOR payload in canonical form
+pub fn canonicalize(raw: &[u8]) -> Result<Vec<u8>, Error> {
+    let ipld: Ipld =
+        serde_ipld_dagcbor::from_slice(raw).map_err(|e| Error::InvalidPayload(e.to_string()))?;
+    encode(&ipld)
+}
+
+impl Varsig {
+    /// verify this varsig over IPLD data, signed as canonical DAG-CBOR. see
+    /// [`Varsig::verify`] for the supported algorithms.
+    pub fn verify_ipld(&self, public_key: &[u8], ipld: &Ipld) -> Result<(), Error> {
+        self.verify(public_key, &encode(ipld)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload;
+    use multicodec::Codec;
+
+    #[test]
+    fn test_canonicalize() {
+        // {"bb": 1, "a": 2} with the longer key first
+        let raw = [0xa2, 0x62, 0x62, 0x62, 0x01, 0x61, 0x61, 0x02];
+        let canonical = [0xa2, 0x61, 0x61, 0x02, 0x62, 0x62, 0x62, 0x01].to_vec();
+        assert_eq!(canonicalize(&raw).unwrap(), canonical);
+        assert_eq!(
+            payload::canonicalize(Codec::DagCbor, &raw).unwrap(),
+            canonical
+        );
+        assert_eq!(canonicalize(&canonical).unwrap(), canonical);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            canonicalize(&[0xa2, 0x61]),
+            Err(Error::InvalidPayload(_))
+        ));
+    }
+}
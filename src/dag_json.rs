@@ -0,0 +1,37 @@
+use crate::{dag_cbor, Error};
+use ipld_core::ipld::Ipld;
+
+/// re-encode a DAG-JSON payload as canonical DAG-CBOR, the form the same
+/// data is signed in whichever of the two encodings it travels in
+pub fn canonicalize(raw: &[u8]) -> Result<Vec<u8>, Error> {
+    let ipld: Ipld =
+        serde_ipld_dagjson::from_slice(raw).map_err(|e| Error::InvalidPayload(e.to_string()))?;
+    dag_cbor::encode(&ipld)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload;
+    use multicodec::Codec;
+
+    #[test]
+    fn test_canonicalize() {
+        let canonical = [0xa2, 0x61, 0x61, 0x02, 0x62, 0x62, 0x62, 0x01].to_vec();
+        assert_eq!(canonicalize(br#"{"bb": 1, "a": 2}"#).unwrap(), canonical);
+        assert_eq!(
+            payload::canonicalize(Codec::DagJson, br#"{"a":2,"bb":1}"#).unwrap(),
+            canonical
+        );
+        // the same data signed as DAG-CBOR canonicalizes identically
+        assert_eq!(
+            payload::canonicalize(Codec::DagCbor, &canonical).unwrap(),
+            canonical
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(canonicalize(b"{"), Err(Error::InvalidPayload(_))));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{Error, Varsig};
+use multicodec::Codec;
+use multitrait::TryDecodeFrom;
+use multiutil::CodecInfo;
+
+/// the did:key method prefix
+const DID_KEY: &str = "did:key:";
+
+impl Varsig {
+    /// verify this varsig over msg with the public key in a did:key DID, e.g.
+    /// `did:key:z6Mk...`. the key codec in the DID must match the varsig's
+    /// and the algorithm's crypto backend feature must be enabled, see
+    /// [`Varsig::verify`].
+    pub fn verify_did_key(&self, did: &str, msg: &[u8]) -> Result<(), Error> {
+        let key = did
+            .strip_prefix(DID_KEY)
+            .ok_or_else(|| Error::InvalidDid(format!("not a did:key: {}", did)))?;
+        // the DID may carry a fragment naming the verification method
+        let key = key.split('#').next().unwrap_or(key);
+        let (_, bytes) = multibase::decode(key)?;
+        let (codec, public_key) = Codec::try_decode_from(bytes.as_slice())?;
+        if codec.code() != self.code() {
+            return Err(Error::InvalidPublicKey(format!(
+                "did:key is a {:?} key, not a {:?} key",
+                codec,
+                self.codec()
+            )));
+        }
+        self.verify(public_key, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+    #[cfg(feature = "ed25519-dalek")]
+    use multibase::Base;
+
+    #[cfg(feature = "ed25519-dalek")]
+    #[test]
+    fn test_verify_did_key() {
+        use multicodec::Codec;
+
+        let sk = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let vs = crate::sign::ed25519(&sk, b"for great justice", Codec::Raw);
+        let mut key = vec![0xed, 0x01];
+        key.extend_from_slice(sk.verifying_key().as_bytes());
+        let did = format!("did:key:{}", multibase::encode(Base::Base58Btc, key));
+        assert!(did.starts_with("did:key:z6Mk"));
+        assert!(vs.verify_did_key(&did, b"for great justice").is_ok());
+        assert!(vs.verify_did_key(&did, b"move every zig").is_err());
+
+        // a secp256k1 did:key doesn't verify an EdDSA varsig
+        let did = format!(
+            "did:key:{}",
+            multibase::encode(Base::Base58Btc, [0xe7, 0x01, 2])
+        );
+        assert!(matches!(
+            vs.verify_did_key(&did, b"for great justice"),
+            Err(Error::InvalidPublicKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_did_key() {
+        let vs = crate::Builder::newv2_private(0x300000).build();
+        assert!(matches!(
+            vs.verify_did_key("did:web:example.com", b"for great justice"),
+            Err(Error::InvalidDid(_))
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::Error;
+use serde_json::{Map, Value};
+use sha3::{Digest, Keccak256};
+
+/// the hash of the typed data's domain, which EIP-712 varsigs record in
+/// their attributes
+pub fn domain_separator(typed_data: &str) -> Result<[u8; 32], Error> {
+    let td = TypedData::parse(typed_data)?;
+    td.hash_struct("EIP712Domain", &td.domain)
+}
+
+/// the EIP-712 digest of a typed-data JSON blob as accepted by
+/// `eth_signTypedData_v4`. this is what the secp256k1 key signed.
+pub fn digest(typed_data: &str) -> Result<[u8; 32], Error> {
+    let td = TypedData::parse(typed_data)?;
+    let mut h = Keccak256::new();
+    h.update([0x19, 0x01]);
+    h.update(td.hash_struct("EIP712Domain", &td.domain)?);
+    h.update(td.hash_struct(&td.primary_type, &td.message)?);
+    Ok(h.finalize().into())
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::InvalidTypedData(msg.into())
+}
+
+struct TypedData {
+    types: Map<String, Value>,
+    primary_type: String,
+    domain: Value,
+    message: Value,
+}
+
+impl TypedData {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let mut v: Value = serde_json::from_str(s).map_err(|e| invalid(e.to_string()))?;
+        let mut take = |key: &str| v.get_mut(key).map(Value::take);
+        let types = match take("types") {
+            Some(Value::Object(types)) => types,
+            _ => return Err(invalid("missing types")),
+        };
+        let primary_type = match take("primaryType") {
+            Some(Value::String(primary_type)) => primary_type,
+            _ => return Err(invalid("missing primaryType")),
+        };
+        let domain = take("domain").ok_or_else(|| invalid("missing domain"))?;
+        let message = take("message").ok_or_else(|| invalid("missing message"))?;
+        Ok(Self {
+            types,
+            primary_type,
+            domain,
+            message,
+        })
+    }
+
+    /// the (name, type) pairs of a struct type's members
+    fn fields(&self, ty: &str) -> Result<Vec<(&str, &str)>, Error> {
+        let fields = self
+            .types
+            .get(ty)
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(format!("unknown type {}", ty)))?;
+        fields
+            .iter()
+            .map(|f| match (f["name"].as_str(), f["type"].as_str()) {
+                (Some(name), Some(ty)) => Ok((name, ty)),
+                _ => Err(invalid(format!("malformed member of {}", ty))),
+            })
+            .collect()
+    }
+
+    /// the struct types a type refers to, including itself
+    fn dependencies<'a>(&'a self, ty: &'a str, deps: &mut Vec<&'a str>) -> Result<(), Error> {
+        let ty = ty.split('[').next().unwrap_or(ty);
+        if deps.contains(&ty) || !self.types.contains_key(ty) {
+            return Ok(());
+        }
+        deps.push(ty);
+        for (_, member) in self.fields(ty)? {
+            self.dependencies(member, deps)?;
+        }
+        Ok(())
+    }
+
+    /// the primary type followed by its dependencies sorted by name
+    fn encode_type(&self, ty: &str) -> Result<String, Error> {
+        let mut deps = Vec::default();
+        self.dependencies(ty, &mut deps)?;
+        deps.retain(|d| *d != ty);
+        deps.sort_unstable();
+        let mut s = String::default();
+        for t in [ty].into_iter().chain(deps) {
+            let members: Vec<String> = self
+                .fields(t)?
+                .into_iter()
+                .map(|(name, ty)| format!("{} {}", ty, name))
+                .collect();
+            s.push_str(&format!("{}({})", t, members.join(",")));
+        }
+        Ok(s)
+    }
+
+    fn hash_struct(&self, ty: &str, value: &Value) -> Result<[u8; 32], Error> {
+        let mut h = Keccak256::new();
+        h.update(Keccak256::digest(self.encode_type(ty)?.as_bytes()));
+        for (name, member) in self.fields(ty)? {
+            h.update(self.encode_value(member, &value[name])?);
+        }
+        Ok(h.finalize().into())
+    }
+
+    fn encode_value(&self, ty: &str, value: &Value) -> Result<[u8; 32], Error> {
+        // arrays are the hash of their concatenated encoded items
+        if let Some(inner) = ty.strip_suffix(']') {
+            let inner = &inner[..inner.rfind('[').ok_or_else(|| invalid(ty))?];
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid(format!("expected an array for {}", ty)))?;
+            let mut h = Keccak256::new();
+            for item in items {
+                h.update(self.encode_value(inner, item)?);
+            }
+            return Ok(h.finalize().into());
+        }
+        if self.types.contains_key(ty) {
+            return self.hash_struct(ty, value);
+        }
+        let mut word = [0u8; 32];
+        match ty {
+            "string" => {
+                let s = value.as_str().ok_or_else(|| invalid("expected a string"))?;
+                word = Keccak256::digest(s.as_bytes()).into();
+            }
+            "bytes" => word = Keccak256::digest(hex_bytes(value)?).into(),
+            "bool" => {
+                let b = value.as_bool().ok_or_else(|| invalid("expected a bool"))?;
+                word[31] = b as u8;
+            }
+            "address" => {
+                let b = hex_bytes(value)?;
+                if b.len() != 20 {
+                    return Err(invalid("expected a 20 byte address"));
+                }
+                word[12..].copy_from_slice(&b);
+            }
+            _ if ty.starts_with("bytes") => {
+                let b = hex_bytes(value)?;
+                if b.len() > 32 {
+                    return Err(invalid(format!("too many bytes for {}", ty)));
+                }
+                word[..b.len()].copy_from_slice(&b);
+            }
+            _ if ty.starts_with("uint") || ty.starts_with("int") => word = integer(value)?,
+            _ => return Err(invalid(format!("unknown type {}", ty))),
+        }
+        Ok(word)
+    }
+}
+
+fn hex_bytes(value: &Value) -> Result<Vec<u8>, Error> {
+    let s = value.as_str().ok_or_else(|| invalid("expected hex"))?;
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| invalid(e.to_string()))
+}
+
+/// a JSON number or a decimal or 0x hex string as a 256-bit big-endian two's
+/// complement word
+fn integer(value: &Value) -> Result<[u8; 32], Error> {
+    let s = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => return Err(invalid("expected an integer")),
+    };
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.as_str()),
+    };
+    let (radix, digits) = match digits.strip_prefix("0x") {
+        Some(digits) => (16, digits),
+        None => (10, digits),
+    };
+    let mut word = [0u8; 32];
+    for c in digits.chars() {
+        let d = c
+            .to_digit(radix)
+            .ok_or_else(|| invalid(format!("invalid integer {}", s)))?;
+        // word = word * radix + d
+        let mut carry = d;
+        for b in word.iter_mut().rev() {
+            let v = *b as u32 * radix + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return Err(invalid(format!("integer {} is too large", s)));
+        }
+    }
+    if negative {
+        // two's complement
+        let mut carry = 1u16;
+        for b in word.iter_mut().rev() {
+            let v = (!*b) as u16 + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+    }
+    Ok(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the example from the EIP-712 specification
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    #[test]
+    fn test_mail() {
+        assert_eq!(
+            hex::encode(domain_separator(MAIL).unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(digest(MAIL).unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer(&Value::from(1)).unwrap()[31], 1);
+        assert_eq!(integer(&Value::from("0x0100")).unwrap()[30..], [1, 0]);
+        assert_eq!(integer(&Value::from("-1")).unwrap(), [0xff; 32]);
+        assert!(integer(&Value::from("1x")).is_err());
+    }
+}
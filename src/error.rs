@@ -8,6 +8,10 @@ pub enum Error {
     #[error(transparent)]
     Fmt(#[from] std::fmt::Error),
 
+    /// An I/O error
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     /// A multibase conversion error
     #[error(transparent)]
     Multibase(#[from] multibase::Error),
@@ -20,6 +24,10 @@ pub enum Error {
     #[error(transparent)]
     Multiutil(#[from] multiutil::Error),
 
+    /// An ssh-key error
+    #[error(transparent)]
+    SshKey(#[from] ssh_key::Error),
+
     /// Missing sigil 0x34
     #[error("Missing Varsig codec sigil")]
     MissingSigil,
@@ -28,7 +36,155 @@ pub enum Error {
     #[error("Invalid Varsig version {0}")]
     InvalidVersion(u8),
 
+    /// Input ends in the middle of a header field or the signature
+    #[error("Varsig is truncated in the {field} at byte {offset}")]
+    Truncated {
+        /// the field being decoded
+        field: &'static str,
+        /// the offset of the field from the start of the input
+        offset: usize,
+    },
+
+    /// A header field can't be decoded
+    #[error("Invalid varsig {field} at byte {offset}: {reason}")]
+    InvalidField {
+        /// the field being decoded
+        field: &'static str,
+        /// the offset of the field from the start of the input
+        offset: usize,
+        /// why the field is invalid
+        reason: String,
+    },
+
+    /// A declared count or length is over the decoder's limit
+    #[error("Varsig {field} at byte {offset} is {value}, over the limit of {limit}")]
+    LimitExceeded {
+        /// the field being decoded
+        field: &'static str,
+        /// the offset of the field from the start of the input
+        offset: usize,
+        /// the declared count or length
+        value: usize,
+        /// the limit it exceeds
+        limit: usize,
+    },
+
+    /// Attributes are not valid for the signing algorithm
+    #[error("Invalid signature attributes: {0}")]
+    InvalidAttributes(String),
+
+    /// Signature length is not valid for the signing algorithm
+    #[error("Invalid signature length: expected {expected}, got {actual}")]
+    InvalidSignatureLength {
+        /// the length the algorithm requires
+        expected: usize,
+        /// the length that was given
+        actual: usize,
+    },
+
+    /// Checksum on a chunked encoded varsig does not match
+    #[error("Encoded varsig checksum mismatch")]
+    ChecksumMismatch,
+
+    /// Signature string is not in a recognized encoding
+    #[error("Invalid signature encoding: {0}")]
+    InvalidSignatureEncoding(String),
+
+    /// Codec value is not in the private use range
+    #[error("Codec 0x{0:x} is not in the private use range")]
+    NotPrivateUse(u64),
+
+    /// Codec already has a built-in header layout
+    #[error("Codec 0x{0:x} has a built-in header layout")]
+    BuiltInLayout(u64),
+
+    /// DID is not a valid did:key
+    #[error("Invalid DID: {0}")]
+    InvalidDid(String),
+
+    /// Public key is not valid for the signing algorithm
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+
+    /// Signing failed
+    #[error("Signing failed: {0}")]
+    SigningFailed(String),
+
+    /// Buffer is too small for the encoded varsig
+    #[error("Buffer of {available} bytes is too small for {needed} bytes")]
+    BufferTooSmall {
+        /// the encoded length
+        needed: usize,
+        /// the buffer length
+        available: usize,
+    },
+
+    /// Varsig frame is longer than the reader accepts
+    #[error("Varsig frame of {len} bytes is over the {max} byte limit")]
+    FrameTooLarge {
+        /// the frame length
+        len: usize,
+        /// the largest accepted frame length
+        max: usize,
+    },
+
+    /// Bytes left over after a strictly parsed varsig
+    #[error("{0} trailing bytes after the varsig")]
+    TrailingBytes(usize),
+
+    /// Varsig encoding is not in canonical form
+    #[error("Non-canonical varsig encoding")]
+    NonCanonical,
+
+    /// Signing codec this crate has neither built-in support nor a registered
+    /// layout for
+    #[error("Unknown signing codec 0x{0:x}")]
+    UnknownCodec(u64),
+
+    /// Signature verification failed
+    #[error("Varsig signature verification failed")]
+    VerificationFailed,
+
+    /// ECDSA signature is not low-S normalized
+    #[error("Malleable high-S ECDSA signature")]
+    HighS,
+
+    /// Encoding info segments the header version can't express
+    #[error("Invalid encoding info: {0}")]
+    InvalidEncodingInfo(String),
+
+    /// Payload could not be canonicalized in its payload encoding
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+
+    /// CAR file is malformed
+    #[error("Invalid CAR file: {0}")]
+    InvalidCar(String),
+
+    /// COSE message is missing what a varsig needs
+    #[error("Invalid COSE message: {0}")]
+    InvalidCose(String),
+
+    /// IPLD data does not hold a varsig where expected
+    #[error("Invalid IPLD: {0}")]
+    InvalidIpld(String),
+
+    /// JWS is malformed or its header doesn't fit the varsig
+    #[error("Invalid JWS: {0}")]
+    InvalidJws(String),
+
+    /// EIP-712 typed data is malformed
+    #[error("Invalid EIP-712 typed data: {0}")]
+    InvalidTypedData(String),
+
     /// Unsupported signature algorithm
     #[error("Unsupported signature algorithm: {0}")]
     UnsupportedAlgorithm(String),
 }
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", defmt::Display2Format(self))
+    }
+}
@@ -0,0 +1,58 @@
+use multicodec::Codec;
+use sha2::{Sha256, Sha384, Sha512};
+use sha3::{Keccak256, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+
+/// Maps a digest implementation to its multicodec hash codec so that prehashed
+/// signing and verification can record and check the varsig hash attribute.
+/// It is implemented for the sha2 and sha3 hashes; implement it for whichever
+/// other hashing crate's types you use.
+pub trait HashCodec {
+    /// the multicodec value of the hash function
+    const CODEC: Codec;
+}
+
+impl HashCodec for Sha256 {
+    const CODEC: Codec = Codec::Sha2256;
+}
+
+impl HashCodec for Sha384 {
+    const CODEC: Codec = Codec::Sha2384;
+}
+
+impl HashCodec for Sha512 {
+    const CODEC: Codec = Codec::Sha2512;
+}
+
+impl HashCodec for Sha3_224 {
+    const CODEC: Codec = Codec::Sha3224;
+}
+
+impl HashCodec for Sha3_256 {
+    const CODEC: Codec = Codec::Sha3256;
+}
+
+impl HashCodec for Sha3_384 {
+    const CODEC: Codec = Codec::Sha3384;
+}
+
+impl HashCodec for Sha3_512 {
+    const CODEC: Codec = Codec::Sha3512;
+}
+
+impl HashCodec for Keccak256 {
+    const CODEC: Codec = Codec::Keccak256;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_codecs() {
+        assert_eq!(Sha256::CODEC.code(), 0x12);
+        assert_eq!(Sha384::CODEC.code(), 0x20);
+        assert_eq!(Sha512::CODEC.code(), 0x13);
+        assert_eq!(Sha3_256::CODEC.code(), 0x16);
+        assert_eq!(Keccak256::CODEC.code(), 0x1b);
+    }
+}
@@ -0,0 +1,381 @@
+//! A varsig header without its signature.
+//!
+//! The header is encoded just as it is in a varsig, leaving out the signature
+//! and, in v2 headers, the signature length. A v2 header ends after its
+//! counted attributes. A v1 header runs to the end of its input. It can only
+//! be decoded if the layout of the signing codec is known.
+//!
+//! Framed protocols can also decode a varsig in two phases: read the header
+//! from the start of the encoded varsig with [`VarsigHeader::decode_header`],
+//! use [`VarsigHeader::expected_signature_len`] to learn how many more bytes
+//! to read, then finish with [`VarsigHeader::attach_signature`].
+use crate::{
+    alg,
+    cow::{self, decode_attributes},
+    registry,
+    varsig_ref::Attributes,
+    vs::{BIP340, SIGIL},
+    CowVarsig, DecodeLimits, Error, Varsig, VarsigVersion,
+};
+use multicodec::Codec;
+use multitrait::TryDecodeFrom;
+use multiutil::Varuint;
+use std::borrow::Cow;
+
+/// Everything in a varsig but the signature bytes, for protocols that send
+/// the header separately from the raw signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VarsigHeader {
+    pub(crate) version: VarsigVersion,
+    pub(crate) code: u64,
+    pub(crate) msg_encoding: Option<Codec>,
+    pub(crate) encoding_segments: Vec<Codec>,
+    pub(crate) attributes: Vec<u64>,
+}
+
+impl VarsigHeader {
+    /// get the version
+    pub fn version(&self) -> VarsigVersion {
+        self.version
+    }
+
+    /// get the signing codec value, including private use values
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    /// get the signing codec, if it is not a private use codec
+    pub fn codec(&self) -> Option<Codec> {
+        Codec::try_from(self.code).ok()
+    }
+
+    /// get the payload encoding, if the header records one
+    pub fn msg_encoding(&self) -> Option<Codec> {
+        self.msg_encoding
+    }
+
+    /// get the payload encoding segments that follow the payload encoding in
+    /// v1 headers
+    pub fn encoding_segments(&self) -> &[Codec] {
+        &self.encoding_segments
+    }
+
+    /// get the signature-specific attributes
+    pub fn attributes(&self) -> &[u64] {
+        &self.attributes
+    }
+
+    /// attach a signature, checking the varsig against the rules for its
+    /// signing algorithm
+    pub fn attach(self, signature: &[u8]) -> Result<Varsig, Error> {
+        let cv = self.with_signature(signature);
+        cv.validate()?;
+        Ok(cv.into_owned())
+    }
+
+    /// the signature length implied by the signing algorithm and the
+    /// attributes, if it is fixed. secp256k1 ECDSA signatures may or may not
+    /// carry a recovery id, so they have none.
+    pub fn expected_signature_len(&self) -> Option<usize> {
+        match (self.codec(), self.attributes.as_slice()) {
+            // rsa signatures are as long as the key modulus
+            (Some(Codec::RsaPub), [_, key_len, ..]) => Some(*key_len as usize),
+            // bip-340 schnorr signatures are always 64 bytes
+            (Some(Codec::Secp256K1Pub), [BIP340, ..]) => Some(64),
+            (Some(codec), _) => alg::info(codec).and_then(|a| a.signature_len),
+            (None, _) => registry::layout(self.code).and_then(|l| l.signature_len),
+        }
+    }
+
+    /// decode the header at the start of an encoded varsig, which may be
+    /// sigil-tagged, returning the bytes after it. for v2 varsigs these start
+    /// with the signature length; for v1 varsigs they are the signature,
+    /// preceded by any payload encoding segments.
+    pub fn decode_header(input: &[u8]) -> Result<(Self, &[u8]), Error> {
+        let bytes = match Codec::try_decode_from(input) {
+            Ok((sigil, ptr)) if sigil == SIGIL => ptr,
+            _ => input,
+        };
+        Self::decode(bytes, false)
+    }
+
+    /// finish decoding a varsig from the bytes that followed its header,
+    /// returning the bytes after the signature. a v1 signature without an
+    /// expected length runs to the end of the bytes.
+    pub fn attach_signature(self, bytes: &[u8]) -> Result<(Varsig, &[u8]), Error> {
+        // v2 signatures carry their length
+        let len = match (self.version, self.expected_signature_len()) {
+            (VarsigVersion::V1, Some(len)) if bytes.len() < len => {
+                return Err(Error::Truncated {
+                    field: "signature",
+                    offset: self.encoded_len(),
+                })
+            }
+            (VarsigVersion::V1, Some(len)) => len,
+            _ => bytes.len(),
+        };
+        let mut v: Vec<u8> = self.into();
+        v.extend_from_slice(&bytes[..len]);
+        let (vs, rest) = Varsig::try_decode_from(v.as_slice())?;
+        Ok((vs, &bytes[len - rest.len()..]))
+    }
+
+    /// the number of bytes the header encodes to
+    pub fn encoded_len(&self) -> usize {
+        let len = self.with_signature(&[]).encoded_len();
+        match self.version {
+            // leave out the zero signature length
+            VarsigVersion::V1 => len,
+            VarsigVersion::V2 => len - 1,
+        }
+    }
+
+    /// encode into the start of buf, returning the number of bytes written,
+    /// which is [`VarsigHeader::encoded_len`]
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(Error::BufferTooSmall {
+                needed: len,
+                available: buf.len(),
+            });
+        }
+        let v: Vec<u8> = self.with_signature(&[]).into();
+        buf[..len].copy_from_slice(&v[..len]);
+        Ok(len)
+    }
+
+    fn with_signature<'a>(&self, signature: &'a [u8]) -> CowVarsig<'a> {
+        CowVarsig {
+            version: self.version,
+            code: self.code,
+            msg_encoding: self.msg_encoding,
+            encoding_segments: self.encoding_segments.clone(),
+            attributes: self.attributes.clone(),
+            signature: Cow::Borrowed(signature),
+        }
+    }
+}
+
+impl From<&Varsig> for VarsigHeader {
+    fn from(vs: &Varsig) -> Self {
+        let cv = CowVarsig::from(vs);
+        Self {
+            version: cv.version,
+            code: cv.code,
+            msg_encoding: cv.msg_encoding,
+            encoding_segments: cv.encoding_segments,
+            attributes: cv.attributes,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for VarsigHeader {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "VarsigHeader v{=u8} - 0x{=u64:x} ({=usize} attributes)",
+            u8::from(self.version),
+            self.code,
+            self.attributes.len()
+        )
+    }
+}
+
+impl From<VarsigHeader> for Vec<u8> {
+    fn from(header: VarsigHeader) -> Self {
+        let mut v = vec![0; header.encoded_len()];
+        // the buffer is exactly as long as the encoding
+        let _ = header.encode_into(&mut v);
+        v
+    }
+}
+
+impl VarsigHeader {
+    /// decode a header, reading the v1 payload encoding segments up to the
+    /// end of the input only if it is a detached header
+    fn decode(input: &[u8], detached: bool) -> Result<(Self, &[u8]), Error> {
+        let limits = DecodeLimits::default();
+        let (version, ptr) = cow::read_version(input, input)?;
+        let (code, p) = cow::read::<Varuint<u64>>(input, ptr, "signing codec")?;
+        let code = code.to_inner();
+        if let Err(e) = Codec::try_from(code) {
+            if !registry::is_private_use(code) {
+                return Err(Error::InvalidField {
+                    field: "signing codec",
+                    offset: cow::offset(input, ptr),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        let ptr = p;
+        let (msg_encoding, count, ptr) = match version {
+            VarsigVersion::V1 => {
+                // the attribute count is only known from the layout
+                let layout = registry::layout(code).ok_or(Error::UnknownCodec(code))?;
+                (None, layout.attributes, ptr)
+            }
+            VarsigVersion::V2 => {
+                let (msg_encoding, ptr) = cow::read::<Codec>(input, ptr, "payload encoding")?;
+                let (len, p) = cow::read::<Varuint<usize>>(input, ptr, "attribute count")?;
+                (Some(msg_encoding), len.to_inner(), p)
+            }
+        };
+        DecodeLimits::check(
+            "attribute count",
+            cow::offset(input, ptr),
+            count,
+            limits.max_attributes,
+        )?;
+        let (attributes, mut ptr) = decode_attributes(input, count, ptr)?;
+        let attributes = Attributes::new(count, attributes).collect();
+        let mut header = Self {
+            version,
+            code,
+            msg_encoding,
+            encoding_segments: Vec::default(),
+            attributes,
+        };
+        if version == VarsigVersion::V1 {
+            // the payload encoding and, in detached headers, any segments,
+            // which run to the end
+            let (msg_encoding, p) = cow::read::<Codec>(input, ptr, "payload encoding")?;
+            header.msg_encoding = Some(msg_encoding);
+            ptr = p;
+            while detached && !ptr.is_empty() {
+                let (segment, p) = cow::read::<Codec>(input, ptr, "payload encoding segment")?;
+                header.encoding_segments.push(segment);
+                ptr = p;
+            }
+        }
+        Ok((header, ptr))
+    }
+}
+
+impl<'a> TryDecodeFrom<'a> for VarsigHeader {
+    type Error = Error;
+
+    fn try_decode_from(input: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
+        Self::decode(input, true)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VarsigHeader {
+    type Error = Error;
+
+    fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
+        let (header, _) = Self::try_decode_from(s)?;
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, RsaPadding};
+    use multiutil::CodecInfo;
+
+    #[test]
+    fn test_header_roundtrip() {
+        for vs in [
+            Builder::newv2(Codec::RsaPub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_rsa_params(Codec::Sha2256, 256)
+                .with_rsa_padding(RsaPadding::Pss { salt_len: 32 })
+                .with_signature_bytes([1u8; 256].as_slice())
+                .build(),
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_encoding_segment(Codec::DagCbor)
+                .with_hash_codec(Codec::Keccak256)
+                .with_signature_bytes([1u8; 65].as_slice())
+                .build(),
+            Builder::newv1(Codec::Ed25519Pub)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+        ] {
+            let header = vs.header();
+            let v: Vec<u8> = header.clone().into();
+            assert_eq!(v.len(), header.encoded_len());
+            // the header is the varsig encoding up to the signature
+            let full: Vec<u8> = vs.clone().into();
+            let sig_len_len = match vs.version() {
+                VarsigVersion::V1 => 0,
+                VarsigVersion::V2 => cow::varuint_len(vs.signature_bytes().len() as u64),
+            };
+            assert_eq!(
+                v.as_slice(),
+                &full[..full.len() - vs.signature_bytes().len() - sig_len_len]
+            );
+            let (decoded, rest) = VarsigHeader::try_decode_from(v.as_slice()).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(decoded, header);
+            assert_eq!(decoded.attach(vs.signature_bytes()).unwrap(), vs);
+        }
+    }
+
+    #[test]
+    fn test_header_v2_rest() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let mut v: Vec<u8> = vs.header().into();
+        v.extend_from_slice(vs.signature_bytes());
+        let (header, rest) = VarsigHeader::try_decode_from(v.as_slice()).unwrap();
+        assert_eq!(header.codec(), Some(Codec::Ed25519Pub));
+        assert_eq!(rest, vs.signature_bytes());
+        // the signature must fit the algorithm
+        assert!(matches!(
+            header.attach(&rest[1..]),
+            Err(Error::InvalidSignatureLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_two_phase_decode() {
+        let varsigs = [
+            Builder::newv1(Codec::Ed25519Pub)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::RsaPub)
+                .with_rsa_params(Codec::Sha2256, 256)
+                .with_signature_bytes([2u8; 256].as_slice())
+                .build(),
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_schnorr(None)
+                .with_signature_bytes([3u8; 64].as_slice())
+                .build(),
+        ];
+        for vs in varsigs {
+            let mut stream = vs.to_tagged_bytes();
+            stream.extend_from_slice(b"next");
+            let (header, rest) = VarsigHeader::decode_header(&stream).unwrap();
+            assert_eq!(header.codec(), Some(vs.codec()));
+            let len = header.expected_signature_len().unwrap();
+            assert_eq!(len, vs.signature_bytes().len());
+            let (decoded, rest) = header.attach_signature(rest).unwrap();
+            assert_eq!(decoded, vs);
+            assert_eq!(rest, b"next");
+        }
+        let header = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build()
+            .header();
+        assert_eq!(header.expected_signature_len(), None);
+
+        let vs = Builder::newv1(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let stream = vs.to_tagged_bytes();
+        let (header, rest) = VarsigHeader::decode_header(&stream).unwrap();
+        assert!(matches!(
+            header.attach_signature(&rest[..63]),
+            Err(Error::Truncated {
+                field: "signature",
+                ..
+            })
+        ));
+    }
+}
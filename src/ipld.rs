@@ -0,0 +1,180 @@
+//! Varsigs as IPLD data. A varsig is IPLD bytes holding the sigil-tagged
+//! varsig, the same as the [`crate::serde::dag_json`] serde profile writes,
+//! so varsigs embedded by js-ipld and by this crate are interchangeable.
+//! Paths are `/` separated map keys and list indexes, e.g. `"proofs/0/sig"`.
+use crate::{dag_cbor, Error, Varsig};
+use ipld_core::ipld::Ipld;
+
+impl From<&Varsig> for Ipld {
+    fn from(vs: &Varsig) -> Self {
+        Ipld::Bytes(vs.to_tagged_bytes())
+    }
+}
+
+impl From<Varsig> for Ipld {
+    fn from(vs: Varsig) -> Self {
+        Self::from(&vs)
+    }
+}
+
+impl TryFrom<&Ipld> for Varsig {
+    type Error = Error;
+
+    fn try_from(ipld: &Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Bytes(bytes) => Varsig::try_from(bytes.as_slice()),
+            _ => Err(Error::InvalidIpld(
+                "a varsig must be IPLD bytes".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<Ipld> for Varsig {
+    type Error = Error;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        Self::try_from(&ipld)
+    }
+}
+
+/// the path segments, ignoring leading, trailing and doubled separators
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+/// the node a path segment names within a map or list
+fn child<'a>(node: &'a Ipld, segment: &str) -> Option<&'a Ipld> {
+    match node {
+        Ipld::Map(map) => map.get(segment),
+        Ipld::List(list) => segment.parse::<usize>().ok().and_then(|i| list.get(i)),
+        _ => None,
+    }
+}
+
+/// get the varsig at a path within IPLD data
+pub fn extract(ipld: &Ipld, path: &str) -> Result<Varsig, Error> {
+    let mut node = ipld;
+    for segment in segments(path) {
+        node = child(node, segment).ok_or_else(|| {
+            Error::InvalidIpld(format!("nothing at {} in path {}", segment, path))
+        })?;
+    }
+    Varsig::try_from(node)
+}
+
+/// put a varsig at a path within IPLD data. everything but the last segment
+/// must already exist; the last segment is a map key to insert or replace
+/// or the index of a list entry to replace, or one past the end to append.
+pub fn embed(ipld: &mut Ipld, path: &str, vs: &Varsig) -> Result<(), Error> {
+    let missing =
+        |segment: &str| Error::InvalidIpld(format!("nothing at {} in path {}", segment, path));
+    let segments: Vec<&str> = segments(path).collect();
+    let (last, parents) = segments
+        .split_last()
+        .ok_or_else(|| Error::InvalidIpld("empty path".to_string()))?;
+    let mut node = ipld;
+    for segment in parents {
+        node = match node {
+            Ipld::Map(map) => map.get_mut(*segment),
+            Ipld::List(list) => segment.parse::<usize>().ok().and_then(|i| list.get_mut(i)),
+            _ => None,
+        }
+        .ok_or_else(|| missing(segment))?;
+    }
+    match node {
+        Ipld::Map(map) => {
+            map.insert(last.to_string(), vs.into());
+        }
+        Ipld::List(list) => match last.parse::<usize>() {
+            Ok(i) if i < list.len() => list[i] = vs.into(),
+            Ok(i) if i == list.len() => list.push(vs.into()),
+            _ => return Err(missing(last)),
+        },
+        _ => return Err(missing(last)),
+    }
+    Ok(())
+}
+
+/// get the varsig at a path within a DAG-CBOR block
+pub fn extract_from_block(block: &[u8], path: &str) -> Result<Varsig, Error> {
+    let ipld: Ipld =
+        serde_ipld_dagcbor::from_slice(block).map_err(|e| Error::InvalidIpld(e.to_string()))?;
+    extract(&ipld, path)
+}
+
+/// put a varsig at a path within a DAG-CBOR block, returning the new block
+/// in canonical form
+pub fn embed_in_block(block: &[u8], path: &str, vs: &Varsig) -> Result<Vec<u8>, Error> {
+    let mut ipld: Ipld =
+        serde_ipld_dagcbor::from_slice(block).map_err(|e| Error::InvalidIpld(e.to_string()))?;
+    embed(&mut ipld, path, vs)?;
+    dag_cbor::encode(&ipld)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use multicodec::Codec;
+    use std::collections::BTreeMap;
+
+    fn varsig(b: u8) -> Varsig {
+        Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([b; 64].as_slice())
+            .build()
+    }
+
+    #[test]
+    fn test_ipld_conversion() {
+        let vs = varsig(1);
+        let ipld = Ipld::from(&vs);
+        assert_eq!(ipld, Ipld::Bytes(vs.to_tagged_bytes()));
+        assert_eq!(vs, Varsig::try_from(ipld).unwrap());
+        assert!(matches!(
+            Varsig::try_from(Ipld::String("sig".to_string())),
+            Err(Error::InvalidIpld(_))
+        ));
+    }
+
+    #[test]
+    fn test_embed_extract() {
+        let mut ipld = Ipld::Map(BTreeMap::from([(
+            "proofs".to_string(),
+            Ipld::List(vec![Ipld::Map(BTreeMap::default())]),
+        )]));
+        embed(&mut ipld, "proofs/0/sig", &varsig(1)).unwrap();
+        embed(&mut ipld, "proofs/1", &varsig(2)).unwrap();
+        embed(&mut ipld, "/sig/", &varsig(3)).unwrap();
+        assert_eq!(extract(&ipld, "proofs/0/sig").unwrap(), varsig(1));
+        assert_eq!(extract(&ipld, "proofs/1").unwrap(), varsig(2));
+        assert_eq!(extract(&ipld, "sig").unwrap(), varsig(3));
+
+        // missing parents and out of range indexes
+        assert!(embed(&mut ipld, "nope/sig", &varsig(1)).is_err());
+        assert!(embed(&mut ipld, "proofs/5", &varsig(1)).is_err());
+        assert!(extract(&ipld, "proofs/2").is_err());
+        assert!(extract(&ipld, "proofs/0").is_err());
+    }
+
+    #[test]
+    fn test_block() {
+        let block = dag_cbor::encode(&Ipld::Map(BTreeMap::from([(
+            "payload".to_string(),
+            Ipld::String("hi".to_string()),
+        )])))
+        .unwrap();
+        let block = embed_in_block(&block, "sig", &varsig(4)).unwrap();
+        assert_eq!(extract_from_block(&block, "sig").unwrap(), varsig(4));
+        // the bytes are the DAG-CBOR byte string serde writes too
+        let ipld: Ipld = serde_ipld_dagcbor::from_slice(&block).unwrap();
+        let sig = match ipld {
+            Ipld::Map(map) => map["sig"].clone(),
+            _ => panic!("expected a map"),
+        };
+        assert_eq!(
+            serde_ipld_dagcbor::to_vec(&sig).unwrap(),
+            serde_ipld_dagcbor::to_vec(&varsig(4)).unwrap()
+        );
+    }
+}
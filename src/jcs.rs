@@ -0,0 +1,160 @@
+use crate::Error;
+use serde_json::Value;
+
+/// canonicalize a JSON document per RFC 8785: no insignificant whitespace,
+/// object members sorted by the UTF-16 code units of their names, numbers
+/// in their shortest ECMAScript form and strings minimally escaped
+pub fn canonicalize(json: &[u8]) -> Result<Vec<u8>, Error> {
+    let value: Value =
+        serde_json::from_slice(json).map_err(|e| Error::InvalidPayload(e.to_string()))?;
+    let mut out = String::new();
+    write_value(&mut out, &value)?;
+    Ok(out.into_bytes())
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            let n = n
+                .as_f64()
+                .ok_or_else(|| Error::InvalidPayload(format!("unrepresentable number {}", n)))?;
+            write_number(out, n)?;
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, v)?;
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, k);
+                out.push(':');
+                write_value(out, v)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// serde_json escapes exactly the characters JCS requires, the same way
+fn write_string(out: &mut String, s: &str) {
+    out.push_str(&Value::from(s).to_string());
+}
+
+/// write a number the way ECMAScript's Number.prototype.toString does
+fn write_number(out: &mut String, n: f64) -> Result<(), Error> {
+    if !n.is_finite() {
+        return Err(Error::InvalidPayload(format!("non-finite number {}", n)));
+    }
+    if n == 0.0 {
+        out.push('0');
+        return Ok(());
+    }
+    if n < 0.0 {
+        out.push('-');
+    }
+    // the shortest round-tripping digits and the decimal exponent
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().unwrap_or_default() + 1;
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat(-n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload;
+    use multicodec::Codec;
+
+    fn number(n: f64) -> String {
+        let mut out = String::new();
+        write_number(&mut out, n).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_numbers() {
+        // from RFC 8785 appendix B
+        assert_eq!(number(0.0), "0");
+        assert_eq!(number(-0.0), "0");
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(-1.5), "-1.5");
+        assert_eq!(number(1e21), "1e+21");
+        assert_eq!(number(1e20), "100000000000000000000");
+        assert_eq!(number(1e-7), "1e-7");
+        assert_eq!(number(0.000001), "0.000001");
+        assert_eq!(number(9007199254740992.0), "9007199254740992");
+        assert_eq!(number(295147905179352830000.0), "295147905179352830000");
+        assert_eq!(number(4.50), "4.5");
+        assert_eq!(number(2e-3), "0.002");
+        assert_eq!(number(0.000000000000000000000000001), "1e-27");
+        assert_eq!(number(333333333.3333333), "333333333.3333333");
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        // from RFC 8785 section 3.2.2
+        let json = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "€$\u000F\u000aA'B\"\\\\\"\/",
+            "literals": [null, true, false]
+        }"#
+        .as_bytes();
+        let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+        assert_eq!(canonicalize(json).unwrap(), expected.as_bytes());
+        assert_eq!(
+            payload::canonicalize(Codec::Json, json).unwrap(),
+            expected.as_bytes()
+        );
+
+        // members sort by utf-16 code units, not code points
+        let json = r#"{"😀": 1, "ﬁ": 2}"#;
+        assert_eq!(
+            canonicalize(json.as_bytes()).unwrap(),
+            "{\"😀\":1,\"ﬁ\":2}".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(canonicalize(b"{"), Err(Error::InvalidPayload(_))));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::Varsig;
+#[cfg(feature = "jws")]
+use crate::{Builder, Error, RsaPadding};
+use multibase::Base;
+#[cfg(feature = "jws")]
+use multicodec::Codec;
+#[cfg(feature = "jws")]
+use serde_json::{Map, Value};
+
+/// the payload encoding of varsigs over JWS signing input, as listed in the
+/// varsig spec
+pub const JWT: u64 = 0x6a77;
+
+/// the JWS signing input, `BASE64URL(header) || '.' || BASE64URL(payload)`,
+/// that a JWT signature is computed over
+pub fn signing_input(header: &[u8], payload: &[u8]) -> Vec<u8> {
+    format!(
+        "{}.{}",
+        Base::Base64Url.encode(header),
+        Base::Base64Url.encode(payload)
+    )
+    .into_bytes()
+}
+
+/// the JOSE `alg` name of a varsig. JWS signatures are raw, so DER ECDSA
+/// signatures and secp256k1 signatures carrying a recovery id have none.
+#[cfg(feature = "jws")]
+pub fn alg(vs: &Varsig) -> Result<&'static str, Error> {
+    let alg = match vs {
+        Varsig::EdDSA { .. } | Varsig::Ed448 { .. } => "EdDSA",
+        Varsig::Es256K {
+            hash: Codec::Sha2256,
+            der: false,
+            signature,
+            ..
+        } if signature.len() == 64 => "ES256K",
+        Varsig::Es256 {
+            hash: Codec::Sha2256,
+            der: false,
+            ..
+        } => "ES256",
+        Varsig::Rsa { hash, padding, .. } => match (hash, padding) {
+            (Codec::Sha2256, RsaPadding::Pkcs1v15) => "RS256",
+            (Codec::Sha2384, RsaPadding::Pkcs1v15) => "RS384",
+            (Codec::Sha2512, RsaPadding::Pkcs1v15) => "RS512",
+            // jwa fixes the salt length to the hash length
+            (Codec::Sha2256, RsaPadding::Pss { salt_len: 32 }) => "PS256",
+            (Codec::Sha2384, RsaPadding::Pss { salt_len: 48 }) => "PS384",
+            (Codec::Sha2512, RsaPadding::Pss { salt_len: 64 }) => "PS512",
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs))),
+        },
+        _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs))),
+    };
+    Ok(alg)
+}
+
+impl Varsig {
+    /// render as a JWS in compact serialization with a detached payload,
+    /// `BASE64URL(header) || '..' || BASE64URL(signature)`. header is the
+    /// JOSE protected header exactly as it was signed over; it is emitted
+    /// verbatim, since re-serializing it would change the signing input, and
+    /// its `alg` parameter must match the varsig. the varsig must be over
+    /// JWS signing input.
+    #[cfg(feature = "jws")]
+    pub fn to_jws_detached(&self, header: &[u8]) -> Result<String, Error> {
+        if !self.is_jws() {
+            return Err(Error::InvalidJws(format!(
+                "{:?} payload is not JWS signing input",
+                self.msg_encoding()
+            )));
+        }
+        let alg = alg(self)?;
+        let params: Map<String, Value> =
+            serde_json::from_slice(header).map_err(|e| Error::InvalidJws(e.to_string()))?;
+        match params.get("alg") {
+            Some(Value::String(a)) if a == alg => {}
+            Some(a) => {
+                return Err(Error::InvalidJws(format!(
+                    "header alg {} doesn't match the varsig's {}",
+                    a, alg
+                )))
+            }
+            None => return Err(Error::InvalidJws("the header has no alg".to_string())),
+        }
+        Ok(format!(
+            "{}..{}",
+            Base::Base64Url.encode(header),
+            Base::Base64Url.encode(self.signature_bytes())
+        ))
+    }
+
+    /// get the varsig from a JWS in compact serialization, with or without
+    /// its payload, from the `alg` header parameter and the signature. the
+    /// varsig is over the JWS signing input.
+    #[cfg(feature = "jws")]
+    pub fn from_jws(compact: &str) -> Result<Self, Error> {
+        let (header, signature) = match compact.split('.').collect::<Vec<_>>().as_slice() {
+            [header, _, signature] => (
+                Base::Base64Url.decode(header)?,
+                Base::Base64Url.decode(signature)?,
+            ),
+            _ => {
+                return Err(Error::InvalidJws(
+                    "expected three dot separated parts".to_string(),
+                ))
+            }
+        };
+        let header: Map<String, Value> =
+            serde_json::from_slice(&header).map_err(|e| Error::InvalidJws(e.to_string()))?;
+        let builder = match header.get("alg").and_then(Value::as_str) {
+            Some("EdDSA") if signature.len() == 114 => Builder::newv2(Codec::Ed448Pub),
+            Some("EdDSA") => Builder::newv2(Codec::Ed25519Pub),
+            Some("ES256K") => Builder::newv2(Codec::Secp256K1Pub).with_hash_codec(Codec::Sha2256),
+            Some("ES256") => Builder::newv2(Codec::P256Pub).with_hash_codec(Codec::Sha2256),
+            Some("RS256") => rsa(Codec::Sha2256, &signature, RsaPadding::Pkcs1v15),
+            Some("RS384") => rsa(Codec::Sha2384, &signature, RsaPadding::Pkcs1v15),
+            Some("RS512") => rsa(Codec::Sha2512, &signature, RsaPadding::Pkcs1v15),
+            Some("PS256") => rsa(Codec::Sha2256, &signature, RsaPadding::Pss { salt_len: 32 }),
+            Some("PS384") => rsa(Codec::Sha2384, &signature, RsaPadding::Pss { salt_len: 48 }),
+            Some("PS512") => rsa(Codec::Sha2512, &signature, RsaPadding::Pss { salt_len: 64 }),
+            Some(alg) => return Err(Error::UnsupportedAlgorithm(alg.to_string())),
+            None => return Err(Error::InvalidJws("the header has no alg".to_string())),
+        };
+        builder
+            .with_msg_encoding(Codec::try_from(JWT)?)
+            .with_signature_bytes(&signature)
+            .try_build()
+    }
+
+    /// returns true if this varsig is over JWS signing input. verifiers must
+    /// rebuild the signing input with [`signing_input`] from the JOSE header
+    /// and the payload and check the signature against that.
+    pub fn is_jws(&self) -> bool {
+        self.msg_encoding().code() == JWT
+    }
+}
+
+#[cfg(feature = "jws")]
+fn rsa(hash: Codec, signature: &[u8], padding: RsaPadding) -> Builder {
+    Builder::newv2(Codec::RsaPub)
+        .with_rsa_params(hash, signature.len())
+        .with_rsa_padding(padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use multicodec::Codec;
+
+    #[test]
+    fn test_signing_input() {
+        // from RFC 7515 appendix A.1
+        let header = b"{\"typ\":\"JWT\",\r\n \"alg\":\"HS256\"}";
+        let input = signing_input(header, b"");
+        assert_eq!(input, b"eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.");
+    }
+
+    #[test]
+    fn test_is_jws() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(!vs.is_jws());
+        if let Ok(jwt) = Codec::try_from(JWT) {
+            let vs = Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(jwt)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build();
+            assert!(vs.is_jws());
+        }
+    }
+
+    #[cfg(feature = "jws")]
+    #[test]
+    fn test_jws_roundtrip() {
+        let jwt = Codec::try_from(JWT).unwrap();
+        let varsigs = [
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(jwt)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(jwt)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::RsaPub)
+                .with_msg_encoding(jwt)
+                .with_rsa_params(Codec::Sha2256, 256)
+                .with_signature_bytes([3u8; 256].as_slice())
+                .build(),
+        ];
+        for vs in varsigs {
+            let header = format!("{{\"kid\":\"k1\", \"alg\":\"{}\"}}", alg(&vs).unwrap());
+            let compact = vs.to_jws_detached(header.as_bytes()).unwrap();
+            let parts: Vec<&str> = compact.split('.').collect();
+            assert_eq!(parts.len(), 3);
+            assert!(parts[1].is_empty());
+            // the header goes out exactly as given
+            assert_eq!(Base::Base64Url.decode(parts[0]).unwrap(), header.as_bytes());
+            assert_eq!(Varsig::from_jws(&compact).unwrap(), vs);
+        }
+    }
+
+    #[cfg(feature = "jws")]
+    #[test]
+    fn test_jws_sign_verify() {
+        use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        // key order and whitespace that re-serializing would not keep
+        let header = b"{\"typ\":\"JWT\",\r\n \"alg\":\"EdDSA\"}";
+        let payload = b"{\"iss\":\"joe\"}";
+        let sig = sk.sign(&signing_input(header, payload));
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::try_from(JWT).unwrap())
+            .with_signature_bytes(&sig.to_bytes())
+            .build();
+        let compact = vs.to_jws_detached(header).unwrap();
+
+        // a verifier puts the payload back and checks the signing input
+        let parts: Vec<&str> = compact.split('.').collect();
+        let vs2 = Varsig::from_jws(&compact).unwrap();
+        assert_eq!(vs2, vs);
+        let input = format!("{}.{}", parts[0], Base::Base64Url.encode(payload));
+        let sig = Signature::from_slice(vs2.signature_bytes()).unwrap();
+        assert!(sk.verifying_key().verify(input.as_bytes(), &sig).is_ok());
+    }
+
+    #[cfg(feature = "jws")]
+    #[test]
+    fn test_jws_errors() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::try_from(JWT).unwrap())
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        for header in [
+            b"{\"alg\":\"ES256\"}".as_slice(),
+            b"{\"kid\":\"k1\"}".as_slice(),
+            b"not json".as_slice(),
+        ] {
+            assert!(matches!(
+                vs.to_jws_detached(header),
+                Err(Error::InvalidJws(_))
+            ));
+        }
+        // a signature over a raw payload isn't over JWS signing input
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            vs.to_jws_detached(b"{\"alg\":\"EdDSA\"}"),
+            Err(Error::InvalidJws(_))
+        ));
+        // HS256 is a MAC, not a signature
+        let compact = format!(
+            "{}..{}",
+            Base::Base64Url.encode(b"{\"alg\":\"HS256\"}"),
+            Base::Base64Url.encode([1u8; 32])
+        );
+        assert!(matches!(
+            Varsig::from_jws(&compact),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+        assert!(matches!(Varsig::from_jws("a.b"), Err(Error::InvalidJws(_))));
+    }
+}
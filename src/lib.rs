@@ -7,17 +7,131 @@
     unused_qualifications
 )]
 
+/// Signing algorithm metadata
+pub mod alg;
+
+/// Async signing with remote keys
+#[cfg(feature = "async")]
+pub mod async_signer;
+
+/// CAR file verification
+#[cfg(feature = "car")]
+pub mod car;
+
+/// Human-checkable chunked display of encoded varsigs
+pub mod chunked;
+pub use chunked::Chunked;
+
+/// COSE_Sign1 interop
+#[cfg(feature = "cose")]
+pub mod cose;
+
+/// Flexible-ownership varsig that borrows from its input when it can
+pub mod cow;
+pub use cow::{CowVarsig, DecodeLimits};
+
+/// Canonical DAG-CBOR payloads
+#[cfg(feature = "dag-cbor")]
+pub mod dag_cbor;
+
+/// DAG-JSON payloads, signed as canonical DAG-CBOR
+#[cfg(feature = "dag-json")]
+pub mod dag_json;
+
+/// EIP-712 typed data hashing
+#[cfg(feature = "eip712")]
+pub mod eip712;
+
+/// did:key verification
+#[cfg(feature = "did")]
+pub mod did;
+
+/// Hash multicodecs for digest implementations
+#[cfg(any(feature = "digest", feature = "stream"))]
+pub mod hash;
+#[cfg(any(feature = "digest", feature = "stream"))]
+pub use hash::HashCodec;
+
+/// Varsig headers detached from their signatures
+pub mod header;
+pub use header::VarsigHeader;
+
 /// Errors produced by this library
 pub mod error;
 pub use error::Error;
 
+/// Varsigs in IPLD data and DAG-CBOR blocks
+#[cfg(feature = "ipld")]
+pub mod ipld;
+
+/// JSON Canonicalization Scheme (RFC 8785) payloads
+#[cfg(feature = "jcs")]
+pub mod jcs;
+
+/// JWS signing input and compact serialization for varsigs over JWTs
+pub mod jws;
+
+/// ECDSA low-S normalization and strict verification
+pub mod normalize;
+
+/// Payload canonicalization keyed by payload encoding
+pub mod payload;
+
+/// Public key recovery for secp256k1 varsigs
+#[cfg(feature = "k256")]
+pub mod recover;
+
+/// Per-codec settings: private use header layouts and preferred encodings
+pub mod registry;
+
+/// JSON Schema for the human-readable serde forms
+#[cfg(feature = "schemars")]
+pub mod schema;
+
 /// Serde serialization
 #[cfg(feature = "serde")]
 pub mod serde;
 
+/// Varsig producing signing functions for the enabled crypto backends
+#[cfg(any(feature = "ed25519-dalek", feature = "k256", feature = "p256"))]
+pub mod sign;
+
+/// Adapters for the RustCrypto signature traits
+#[cfg(feature = "signature-traits")]
+pub mod signature_traits;
+
+/// Verification against SSH certificate-backed keys
+#[cfg(feature = "ssh-cert")]
+pub mod ssh;
+
+/// Incremental verification of large payloads
+#[cfg(feature = "stream")]
+pub mod stream;
+
+/// Varsig frames over tokio async streams
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+/// Optional metrics instrumentation
+mod telemetry;
+
+/// Helpers shared by the async tests
+#[cfg(all(test, any(feature = "async", feature = "tokio")))]
+mod test_util;
+
+/// Signature verification with feature-gated crypto backends
+pub mod verify;
+
+/// Zero-copy varsig view
+pub mod varsig_ref;
+pub use varsig_ref::VarsigRef;
+
 /// Varsig type and functions
 pub mod vs;
-pub use vs::{Builder, EncodedVarsig, Varsig};
+pub use vs::{
+    Attributes, Builder, EncodedVarsig, RetainedBase, RsaPadding, Varsig, VarsigParts,
+    VarsigVersion,
+};
 
 /// ...and in the darkness bind them
 pub mod prelude {
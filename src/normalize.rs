@@ -0,0 +1,180 @@
+use crate::{Error, Varsig};
+
+/// the order n of the secp256k1 group
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// the order n of the NIST P-256 group
+const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+/// true if the big-endian s is greater than n / 2
+fn is_high(s: &[u8], order: &[u8; 32]) -> bool {
+    let mut half = [0u8; 32];
+    let mut carry = 0u8;
+    for (h, n) in half.iter_mut().zip(order) {
+        *h = (n >> 1) | carry;
+        carry = n << 7;
+    }
+    s > half.as_slice()
+}
+
+/// replace the big-endian s with n - s
+fn negate(s: &mut [u8], order: &[u8; 32]) {
+    let mut borrow = 0i16;
+    for (s, n) in s.iter_mut().zip(order).rev() {
+        let d = *n as i16 - *s as i16 - borrow;
+        borrow = (d < 0) as i16;
+        *s = d.rem_euclid(256) as u8;
+    }
+}
+
+impl Varsig {
+    /// the curve order and the r || s || [v] signature of ECDSA varsigs
+    fn ecdsa_signature(&self) -> Option<(&'static [u8; 32], &[u8])> {
+        // der signatures would have to be re-encoded
+        if self.is_der() {
+            return None;
+        }
+        let order = match self {
+            Varsig::Es256K { .. } | Varsig::Eip191 { .. } | Varsig::Eip712 { .. } => {
+                &SECP256K1_ORDER
+            }
+            Varsig::Es256 { .. } => &P256_ORDER,
+            _ => return None,
+        };
+        Some((order, self.as_ref())).filter(|(_, sig)| sig.len() >= 64)
+    }
+
+    /// whether the s half of an ECDSA signature is in the lower half of the
+    /// curve order. None for anything that isn't an ECDSA varsig with at
+    /// least an r || s signature.
+    pub fn is_low_s(&self) -> Option<bool> {
+        self.ecdsa_signature()
+            .map(|(order, sig)| !is_high(&sig[32..64], order))
+    }
+
+    /// make an ECDSA signature low-S by replacing a high s with n - s, the
+    /// other signature that verifies the same payload, flipping any recovery
+    /// id to match. the header is left alone; the experimental [`LOW_S`]
+    /// flag is only recorded by [`crate::Builder::with_low_s`]. returns true
+    /// if the signature bytes changed.
+    ///
+    /// [`LOW_S`]: crate::vs::LOW_S
+    pub fn normalize(&mut self) -> bool {
+        let (order, high) = match self.ecdsa_signature() {
+            Some((order, sig)) => (order, is_high(&sig[32..64], order)),
+            None => return false,
+        };
+        let signature = match self {
+            Varsig::Es256K { signature, .. }
+            | Varsig::Es256 { signature, .. }
+            | Varsig::Eip191 { signature, .. }
+            | Varsig::Eip712 { signature, .. } => signature,
+            _ => return false,
+        };
+        if high {
+            negate(&mut signature[32..64], order);
+            if let Some(v) = signature.get_mut(64) {
+                *v = match *v {
+                    0 | 1 => *v ^ 1,
+                    27 => 28,
+                    28 => 27,
+                    v => v,
+                };
+            }
+        }
+        high
+    }
+
+    /// like [`Varsig::verify`] but rejects ECDSA signatures that aren't
+    /// low-S with [`Error::HighS`], for users that need non-malleable
+    /// signatures
+    pub fn verify_strict(&self, public_key: &[u8], msg: &[u8]) -> Result<(), Error> {
+        if self.is_low_s() == Some(false) {
+            return Err(Error::HighS);
+        }
+        self.verify(public_key, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, VarsigVersion};
+    use multicodec::Codec;
+
+    fn es256k(s: u8) -> Varsig {
+        let mut sig = [0x11u8; 64].to_vec();
+        sig[32..].copy_from_slice(&[s; 32]);
+        sig.push(27);
+        Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes(sig.as_slice())
+            .build()
+    }
+
+    #[test]
+    fn test_negate() {
+        let mut s = [0u8; 32];
+        s[31] = 1;
+        negate(&mut s, &SECP256K1_ORDER);
+        let mut expected = SECP256K1_ORDER;
+        expected[31] -= 1;
+        assert_eq!(s, expected);
+        negate(&mut s, &SECP256K1_ORDER);
+        assert_eq!(s[31], 1);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut vs = es256k(0xf0);
+        assert_eq!(vs.is_low_s(), Some(false));
+        assert!(vs.normalize());
+        assert_eq!(vs.is_low_s(), Some(true));
+        assert_eq!(vs.as_ref()[64], 28);
+
+        // the header doesn't pick up the experimental flag
+        assert!(matches!(vs, Varsig::Es256K { low_s: false, .. }));
+        assert_eq!(vs.attributes(), [Codec::Keccak256.code()].to_vec());
+
+        // normalizing again changes nothing
+        assert!(!vs.normalize());
+        assert_eq!(vs.as_ref()[64], 28);
+
+        // low-S signatures are left alone
+        let mut vs = es256k(0x01);
+        let before = vs.clone();
+        assert!(!vs.normalize());
+        assert_eq!(vs, before);
+    }
+
+    #[test]
+    fn test_normalize_v1() {
+        let mut vs = Builder::newv1(Codec::P256Pub)
+            .with_hash_codec(Codec::try_from(0x12).unwrap())
+            .with_signature_bytes([0xf0u8; 64].as_slice())
+            .build();
+        assert!(vs.normalize());
+        assert_eq!(vs.version(), VarsigVersion::V1);
+        assert!(matches!(vs, Varsig::Es256 { low_s: false, .. }));
+        assert_eq!(vs.is_low_s(), Some(true));
+    }
+
+    #[test]
+    fn test_verify_strict() {
+        let vs = es256k(0xf0);
+        assert!(matches!(
+            vs.verify_strict(&[], b"for great justice"),
+            Err(Error::HighS)
+        ));
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert_eq!(vs.is_low_s(), None);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{Error, Varsig};
+use multicodec::Codec;
+use multitrait::TryDecodeFrom;
+use multiutil::Varuint;
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+/// Turns a payload in some encoding into the exact bytes that get signed.
+/// Register one with [`register`] to support a payload encoding this crate
+/// doesn't know, or to replace a built-in transform.
+pub trait Canonicalizer: Send + Sync {
+    /// canonicalize the raw payload
+    fn canonicalize(&self, raw: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+impl<F> Canonicalizer for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>, Error> + Send + Sync,
+{
+    fn canonicalize(&self, raw: &[u8]) -> Result<Vec<u8>, Error> {
+        self(raw)
+    }
+}
+
+fn canonicalizers() -> &'static RwLock<HashMap<u64, Arc<dyn Canonicalizer>>> {
+    static CANONICALIZERS: OnceLock<RwLock<HashMap<u64, Arc<dyn Canonicalizer>>>> = OnceLock::new();
+    CANONICALIZERS.get_or_init(Default::default)
+}
+
+/// register the canonicalizer for a payload encoding, replacing any
+/// previously registered one and taking precedence over the built-in
+/// transform
+pub fn register(msg_encoding: Codec, canonicalizer: Arc<dyn Canonicalizer>) {
+    canonicalizers()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(msg_encoding.code(), canonicalizer);
+}
+
+/// remove the canonicalizer registered for a payload encoding
+pub fn unregister(msg_encoding: Codec) -> Option<Arc<dyn Canonicalizer>> {
+    canonicalizers()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&msg_encoding.code())
+}
+
+/// the EIP-191 personal_sign message: the prefix, the decimal payload length
+/// and the payload
+pub(crate) fn eip191(raw: &[u8]) -> Vec<u8> {
+    let mut msg = format!("\x19Ethereum Signed Message:\n{}", raw.len()).into_bytes();
+    msg.extend_from_slice(raw);
+    msg
+}
+
+/// transform a payload in the given encoding into the bytes that get signed.
+/// registered canonicalizers win, then the built-in transforms:
+///
+/// * EIP-191: the personal_sign prefix and length are prepended
+/// * DAG-CBOR: re-encoded in canonical form, with the `dag-cbor` feature
+/// * DAG-JSON: re-encoded as canonical DAG-CBOR so that signatures carry
+///   over between the two encodings, with the `dag-json` feature
+/// * JSON: canonicalized per RFC 8785 (JCS), with the `jcs` feature
+///
+/// payloads in any other encoding, raw included, pass through unchanged
+pub fn canonicalize(msg_encoding: Codec, raw: &[u8]) -> Result<Vec<u8>, Error> {
+    let registered = canonicalizers()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&msg_encoding.code())
+        .cloned();
+    if let Some(canonicalizer) = registered {
+        return canonicalizer.canonicalize(raw);
+    }
+    Ok(match msg_encoding {
+        Codec::Eip191 => eip191(raw),
+        #[cfg(feature = "dag-cbor")]
+        Codec::DagCbor => crate::dag_cbor::canonicalize(raw)?,
+        #[cfg(feature = "dag-json")]
+        Codec::DagJson => crate::dag_json::canonicalize(raw)?,
+        #[cfg(feature = "jcs")]
+        Codec::Json => crate::jcs::canonicalize(raw)?,
+        _ => raw.to_vec(),
+    })
+}
+
+/// split a multihash into its hash codec and digest
+pub fn decode_multihash(multihash: &[u8]) -> Result<(Codec, &[u8]), Error> {
+    let (codec, ptr) = Codec::try_decode_from(multihash)?;
+    let (len, digest) = Varuint::<usize>::try_decode_from(ptr)?;
+    if digest.len() != len.to_inner() {
+        return Err(Error::InvalidPayload(format!(
+            "expected a {} byte digest, got {}",
+            len.to_inner(),
+            digest.len()
+        )));
+    }
+    Ok((codec, digest))
+}
+
+impl Varsig {
+    /// verify this varsig over a payload in its payload encoding, first
+    /// canonicalizing it with [`canonicalize`]. see [`Varsig::verify`] for
+    /// the supported algorithms.
+    pub fn verify_payload(&self, public_key: &[u8], raw: &[u8]) -> Result<(), Error> {
+        match self {
+            // the algorithm itself applies the EIP-191 prefix
+            Varsig::Eip191 { .. } => self.verify(public_key, raw),
+            _ => self.verify(public_key, &canonicalize(self.msg_encoding(), raw)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin() {
+        assert_eq!(canonicalize(Codec::Raw, b"zig").unwrap(), b"zig".to_vec());
+        assert_eq!(
+            canonicalize(Codec::Eip191, b"zig").unwrap(),
+            b"\x19Ethereum Signed Message:\n3zig".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_decode_multihash() {
+        let mut mh = vec![0x12, 0x20];
+        mh.extend_from_slice(&[7u8; 32]);
+        let (codec, digest) = decode_multihash(&mh).unwrap();
+        assert_eq!(codec.code(), 0x12);
+        assert_eq!(digest, [7u8; 32].as_slice());
+        assert!(matches!(
+            decode_multihash(&mh[..20]),
+            Err(Error::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn test_register() {
+        // sha2-256
+        let codec = Codec::try_from(0x12).unwrap();
+        register(
+            codec,
+            Arc::new(|raw: &[u8]| Ok::<_, Error>(raw.to_ascii_uppercase())),
+        );
+        assert_eq!(canonicalize(codec, b"zig").unwrap(), b"ZIG".to_vec());
+        assert!(unregister(codec).is_some());
+        assert_eq!(canonicalize(codec, b"zig").unwrap(), b"zig".to_vec());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Error, Varsig};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use multicodec::Codec;
+use sha3::{Digest, Keccak256};
+
+impl Varsig {
+    /// recover the secp256k1 public key that signed msg from an ES256K,
+    /// EIP-191 or EIP-712 varsig with a recovery id. msg is the payload for
+    /// ES256K and EIP-191 varsigs and the hashStruct of the typed data
+    /// message for EIP-712 varsigs.
+    pub fn recover_public_key(&self, msg: &[u8]) -> Result<VerifyingKey, Error> {
+        let prehash = self.keccak_prehash(msg)?;
+        let recovery_id = self
+            .recovery_id()
+            .and_then(RecoveryId::from_byte)
+            .ok_or_else(|| Error::InvalidSignatureLength {
+                expected: 65,
+                actual: self.as_ref().len(),
+            })?;
+        let sig =
+            Signature::from_slice(&self.as_ref()[..64]).map_err(|_| Error::VerificationFailed)?;
+        VerifyingKey::recover_from_prehash(&prehash, &sig, recovery_id)
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    /// the Keccak256 prehash a secp256k1 key signs for ES256K varsigs using
+    /// Keccak256, EIP-191 varsigs and EIP-712 varsigs
+    pub(crate) fn keccak_prehash(&self, msg: &[u8]) -> Result<[u8; 32], Error> {
+        Ok(match self {
+            Varsig::Es256K {
+                hash: Codec::Keccak256,
+                ..
+            } => Keccak256::digest(msg).into(),
+            Varsig::Eip191 { .. } => Keccak256::digest(crate::payload::eip191(msg)).into(),
+            Varsig::Eip712 {
+                domain_separator, ..
+            } => Keccak256::new()
+                .chain_update([0x19, 0x01])
+                .chain_update(domain_separator)
+                .chain_update(msg)
+                .finalize()
+                .into(),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        })
+    }
+
+    /// recover the Ethereum address of the key that signed msg, see
+    /// [`Varsig::recover_public_key`]
+    pub fn recover_address(&self, msg: &[u8]) -> Result<[u8; 20], Error> {
+        let key = self.recover_public_key(msg)?;
+        let point = key.to_encoded_point(false);
+        let hash = Keccak256::digest(&point.as_bytes()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use k256::ecdsa::SigningKey;
+
+    #[test]
+    fn test_recover_eip191() {
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let msg = b"for great justice";
+        let prehash = Keccak256::new()
+            .chain_update(format!("\x19Ethereum Signed Message:\n{}", msg.len()))
+            .chain_update(msg)
+            .finalize();
+        let (sig, rid) = sk.sign_prehash_recoverable(&prehash).unwrap();
+        let mut bytes = sig.to_bytes().to_vec();
+        bytes.push(rid.to_byte() + 27);
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes(bytes.as_slice())
+            .build();
+        assert_eq!(vs.recover_public_key(msg).unwrap(), *sk.verifying_key());
+        assert_ne!(
+            vs.recover_public_key(b"move every zig").ok(),
+            Some(*sk.verifying_key())
+        );
+        assert!(vs.recover_address(msg).is_ok());
+    }
+
+    #[test]
+    fn test_no_recovery_id() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(vs.recover_public_key(b"for great justice").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{alg, Error};
+use multibase::Base;
+use multicodec::Codec;
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{OnceLock, RwLock},
+};
+
+/// the range of multicodec values reserved for private use
+pub const PRIVATE_USE: RangeInclusive<u64> = 0x300000..=0x3fffff;
+
+/// returns true if the codec value is in the private use range
+pub fn is_private_use(code: u64) -> bool {
+    PRIVATE_USE.contains(&code)
+}
+
+/// Describes the header layout of a signing codec so that v1 varsigs using it
+/// can be decoded and varsigs using it can be validated. v1 headers put the
+/// signature-specific attributes before the payload encoding without a count,
+/// so the decoder cannot find the payload encoding unless it knows how many
+/// attributes there are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// the number of signature-specific attribute varuints
+    pub attributes: usize,
+    /// the length of the signature data, if it is fixed
+    pub signature_len: Option<usize>,
+}
+
+impl Layout {
+    /// create a new layout
+    pub fn new(attributes: usize, signature_len: Option<usize>) -> Self {
+        Self {
+            attributes,
+            signature_len,
+        }
+    }
+}
+
+fn layouts() -> &'static RwLock<HashMap<u64, Layout>> {
+    static LAYOUTS: OnceLock<RwLock<HashMap<u64, Layout>>> = OnceLock::new();
+    LAYOUTS.get_or_init(Default::default)
+}
+
+/// register the header layout for a private use signing codec, replacing any
+/// previously registered layout for the same codec
+pub fn register_private(code: u64, layout: Layout) -> Result<(), Error> {
+    if !is_private_use(code) {
+        return Err(Error::NotPrivateUse(code));
+    }
+    layouts()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(code, layout);
+    Ok(())
+}
+
+/// register the header layout for a signing codec this crate has no built-in
+/// support for, replacing any previously registered layout for the same
+/// codec. varsigs using it still decode as [`crate::Varsig::Unknown`] but v1
+/// headers are parsed into their attributes, payload encoding and signature
+/// and validation checks them against the layout.
+pub fn register(codec: Codec, layout: Layout) -> Result<(), Error> {
+    if builtin_layout(codec.code()).is_some() {
+        return Err(Error::BuiltInLayout(codec.code()));
+    }
+    layouts()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(codec.code(), layout);
+    Ok(())
+}
+
+/// remove the header layout registered for a signing codec
+pub fn unregister(codec: Codec) -> Option<Layout> {
+    unregister_private(codec.code())
+}
+
+/// get the header layout of a signing codec, either built in for the
+/// algorithms this crate knows or registered
+pub fn layout(code: u64) -> Option<Layout> {
+    // the registry is a lazily created map behind a lock, which the model
+    // checker can't practically explore, so proofs only see the built-in
+    // layouts
+    #[cfg(kani)]
+    return builtin_layout(code);
+    #[cfg(not(kani))]
+    builtin_layout(code).or_else(|| private_layout(code))
+}
+
+/// the header layouts of the signing codecs this crate knows
+fn builtin_layout(code: u64) -> Option<Layout> {
+    let codec = Codec::try_from(code).ok()?;
+    let attributes = match codec {
+        Codec::Ed25519Pub | Codec::Ed448Pub => 0,
+        // the hash codec
+        Codec::Secp256K1Pub | Codec::P256Pub => 1,
+        // the hash codec and the key length in bytes, which is also the
+        // signature length
+        Codec::RsaPub => 2,
+        _ => return None,
+    };
+    Some(Layout::new(attributes, alg::info(codec)?.signature_len))
+}
+
+/// remove the header layout for a private use signing codec
+pub fn unregister_private(code: u64) -> Option<Layout> {
+    layouts()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&code)
+}
+
+/// look up the registered header layout for a signing codec
+pub fn private_layout(code: u64) -> Option<Layout> {
+    layouts()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&code)
+        .copied()
+}
+
+fn encodings() -> &'static RwLock<HashMap<u64, Base>> {
+    static ENCODINGS: OnceLock<RwLock<HashMap<u64, Base>>> = OnceLock::new();
+    ENCODINGS.get_or_init(Default::default)
+}
+
+fn default_override() -> &'static RwLock<Option<Base>> {
+    static DEFAULT: OnceLock<RwLock<Option<Base>>> = OnceLock::new();
+    DEFAULT.get_or_init(Default::default)
+}
+
+/// the built-in preferred base encoding for a signing codec. UCANs are the
+/// main consumer of EdDSA varsigs and they use base64url; everything else,
+/// Ethereum's secp256k1 varsigs included, is base16 lower. callers that need
+/// the base16 strings EdDSA varsigs had before can set that per codec or
+/// crate-wide.
+fn builtin_encoding(code: u64) -> Base {
+    match Codec::try_from(code) {
+        Ok(Codec::Ed25519Pub) => Base::Base64Url,
+        _ => Base::Base16Lower,
+    }
+}
+
+/// serializes the tests that change the preferred encodings or depend on
+/// them, since they are process-wide
+#[cfg(test)]
+static ENCODING_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// take the lock on the preferred encodings for the rest of a test
+#[cfg(test)]
+pub(crate) fn lock_encodings() -> std::sync::MutexGuard<'static, ()> {
+    ENCODING_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// set the preferred base encoding for a signing codec, or clear it with None
+/// to go back to the built-in preference
+pub fn set_preferred_encoding(code: u64, base: Option<Base>) {
+    let mut encodings = encodings().write().unwrap_or_else(|e| e.into_inner());
+    match base {
+        Some(base) => encodings.insert(code, base),
+        None => encodings.remove(&code),
+    };
+}
+
+/// set a crate-wide base encoding that overrides the per-codec preferences,
+/// or clear it with None
+pub fn set_default_encoding(base: Option<Base>) {
+    *default_override()
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = base;
+}
+
+/// get the crate-wide default base encoding
+pub fn default_encoding() -> Base {
+    default_override()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .unwrap_or(Base::Base16Lower)
+}
+
+/// get the preferred base encoding for a signing codec. the crate-wide
+/// setting wins, then any per-codec setting, then the built-in preference:
+/// base64url for EdDSA and base16 lower for everything else.
+pub fn preferred_encoding(code: u64) -> Base {
+    if let Some(base) = *default_override().read().unwrap_or_else(|e| e.into_inner()) {
+        return base;
+    }
+    encodings()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&code)
+        .copied()
+        .unwrap_or_else(|| builtin_encoding(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register() {
+        let code = 0x300001;
+        assert!(private_layout(code).is_none());
+        register_private(code, Layout::new(1, Some(32))).unwrap();
+        assert_eq!(private_layout(code), Some(Layout::new(1, Some(32))));
+        assert_eq!(unregister_private(code), Some(Layout::new(1, Some(32))));
+        assert!(private_layout(code).is_none());
+    }
+
+    #[test]
+    fn test_preferred_encoding() {
+        let _lock = lock_encodings();
+        let code = 0x300002;
+        assert_eq!(preferred_encoding(code), Base::Base16Lower);
+        assert_eq!(
+            preferred_encoding(Codec::Ed25519Pub.code()),
+            Base::Base64Url
+        );
+        assert_eq!(
+            preferred_encoding(Codec::Secp256K1Pub.code()),
+            Base::Base16Lower
+        );
+        set_preferred_encoding(code, Some(Base::Base58Btc));
+        assert_eq!(preferred_encoding(code), Base::Base58Btc);
+
+        // the crate-wide setting wins over the per-codec one
+        set_default_encoding(Some(Base::Base64Url));
+        assert_eq!(preferred_encoding(code), Base::Base64Url);
+        assert_eq!(default_encoding(), Base::Base64Url);
+        set_default_encoding(None);
+        assert_eq!(preferred_encoding(code), Base::Base58Btc);
+
+        set_preferred_encoding(code, None);
+        assert_eq!(preferred_encoding(code), Base::Base16Lower);
+    }
+
+    #[test]
+    fn test_register_codec() {
+        // bls12-381 g1
+        let codec = Codec::try_from(0xea).unwrap();
+        assert!(layout(codec.code()).is_none());
+        register(codec, Layout::new(1, Some(96))).unwrap();
+        assert_eq!(layout(codec.code()), Some(Layout::new(1, Some(96))));
+        assert_eq!(unregister(codec), Some(Layout::new(1, Some(96))));
+        assert!(layout(codec.code()).is_none());
+
+        // built-in layouts can't be replaced
+        assert!(register(Codec::Ed25519Pub, Layout::default()).is_err());
+    }
+
+    #[test]
+    fn test_not_private() {
+        assert!(register_private(0xed, Layout::default()).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! JSON Schema for the human-readable serde forms of varsigs, for API servers
+//! that generate OpenAPI documents.
+use crate::Varsig;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{ArrayValidation, InstanceType, ObjectValidation, Schema, SchemaObject},
+    JsonSchema,
+};
+use std::borrow::Cow;
+
+/// a multibase encoded string
+fn multibase(description: &str) -> SchemaObject {
+    let mut schema = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        ..Default::default()
+    };
+    schema.metadata().description = Some(description.to_string());
+    schema
+}
+
+/// The five-field struct the default serde implementation writes in
+/// human-readable formats
+impl JsonSchema for Varsig {
+    fn schema_name() -> String {
+        "Varsig".to_string()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Borrowed(concat!(module_path!(), "::Varsig"))
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut version = SchemaObject {
+            instance_type: Some(InstanceType::Integer.into()),
+            enum_values: Some(vec![1.into(), 2.into()]),
+            ..Default::default()
+        };
+        version.metadata().description = Some("varsig header version".to_string());
+        let mut codec = gen.subschema_for::<u64>().into_object();
+        codec.metadata().description = Some("signing multicodec value".to_string());
+        let mut encoding = gen.subschema_for::<Option<u64>>().into_object();
+        encoding.metadata().description = Some(
+            "payload encoding multicodec value, null for v1 varsigs decoded without a known layout"
+                .to_string(),
+        );
+        let mut attributes = SchemaObject {
+            instance_type: Some(InstanceType::Array.into()),
+            array: Some(Box::new(ArrayValidation {
+                items: Some(Schema::from(multibase("multibase encoded varuint attribute")).into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        attributes.metadata().description = Some("signature-specific attributes".to_string());
+        let signature = multibase("multibase encoded, length prefixed signature data");
+        let mut segments = gen.subschema_for::<Vec<u64>>().into_object();
+        segments.metadata().description =
+            Some("further v1 payload encoding multicodec values, if any".to_string());
+
+        let mut object = ObjectValidation::default();
+        for (name, schema) in [
+            ("version", version),
+            ("codec", codec),
+            ("encoding", encoding),
+            ("attributes", attributes),
+            ("signature", signature),
+        ] {
+            object.properties.insert(name.to_string(), schema.into());
+            object.required.insert(name.to_string());
+        }
+        object
+            .properties
+            .insert("segments".to_string(), segments.into());
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            object: Some(Box::new(object)),
+            ..Default::default()
+        };
+        schema.metadata().description = Some("a varsig self-describing signature".to_string());
+        schema.into()
+    }
+}
+
+/// The schema of an [`crate::EncodedVarsig`], and of varsig fields using
+/// [`crate::serde::string`] or [`crate::serde::as_multibase`]: a multibase
+/// string. `EncodedVarsig` is a `multiutil` type so it can't implement
+/// [`JsonSchema`] itself; use `#[schemars(with = "EncodedVarsigSchema")]`.
+pub struct EncodedVarsigSchema;
+
+impl JsonSchema for EncodedVarsigSchema {
+    fn schema_name() -> String {
+        "EncodedVarsig".to_string()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Borrowed(concat!(module_path!(), "::EncodedVarsig"))
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        multibase("a multibase encoded varsig").into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, EncodedVarsig};
+    use multibase::Base;
+    use multicodec::Codec;
+
+    #[test]
+    fn test_varsig_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(Varsig)).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let value = serde_json::to_value(&vs).unwrap();
+        // the schema requires exactly the fields serde always writes
+        let mut fields: Vec<_> = value.as_object().unwrap().keys().collect();
+        let mut required: Vec<_> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        fields.sort();
+        required.sort();
+        assert_eq!(fields, required);
+        assert!(properties.contains_key("segments"));
+        assert_eq!(properties["signature"]["type"], "string");
+        assert_eq!(properties["attributes"]["items"]["type"], "string");
+        assert_eq!(properties["version"]["enum"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_encoded_varsig_schema() {
+        #[derive(serde::Serialize, JsonSchema)]
+        struct Token {
+            #[schemars(with = "EncodedVarsigSchema")]
+            sig: EncodedVarsig,
+        }
+
+        let schema = serde_json::to_value(schemars::schema_for!(Token)).unwrap();
+        assert_eq!(
+            schema["definitions"]["EncodedVarsig"]["type"],
+            serde_json::json!("string")
+        );
+        let token = Token {
+            sig: Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build()
+                .to_encoded(Base::Base58Btc),
+        };
+        assert!(serde_json::to_value(&token).unwrap()["sig"].is_string());
+    }
+}
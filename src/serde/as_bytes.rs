@@ -0,0 +1,30 @@
+//! Serde (de)serialization of a [`Varsig`] field as the sigil-tagged varsig
+//! bytes in every format. Untagged varsigs are accepted when deserializing.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Token {
+//!     #[serde(with = "varsig::serde::as_bytes")]
+//!     sig: Varsig,
+//! }
+//! ```
+use super::dag_json::BytesVisitor;
+use crate::Varsig;
+use serde::{de::Error, Deserializer, Serializer};
+
+/// serialize a varsig as a byte string
+pub fn serialize<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(&vs.to_tagged_bytes())
+}
+
+/// deserialize a varsig from a byte string
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+    Varsig::try_from(bytes.as_slice()).map_err(|e| D::Error::custom(e.to_string()))
+}
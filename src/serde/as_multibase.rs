@@ -0,0 +1,33 @@
+//! Serde (de)serialization of a [`Varsig`] field as a multibase string in
+//! every format, human-readable or not. Use [`crate::serde::string`] to keep
+//! the compact encoding in binary formats.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Token {
+//!     #[serde(with = "varsig::serde::as_multibase")]
+//!     sig: Varsig,
+//! }
+//! ```
+use crate::{EncodedVarsig, Varsig};
+use multiutil::EncodingInfo;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+/// serialize a varsig as a multibase string in its preferred base encoding
+pub fn serialize<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(&vs.to_encoded(vs.encoding()))
+}
+
+/// deserialize a varsig from a multibase string in any base encoding
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    EncodedVarsig::try_from(s.as_str())
+        .map(|e| e.to_inner())
+        .map_err(|e| D::Error::custom(e.to_string()))
+}
@@ -0,0 +1,29 @@
+//! Serde (de)serialization of a [`Varsig`] field as the five-field struct of
+//! version, codec, encoding, attributes and signature in every format, not
+//! just human-readable ones.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Token {
+//!     #[serde(with = "varsig::serde::as_struct")]
+//!     sig: Varsig,
+//! }
+//! ```
+use crate::{DecodeLimits, Varsig};
+use serde::{Deserializer, Serializer};
+
+/// serialize a varsig as a struct
+pub fn serialize<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    super::ser::serialize_struct(vs, serializer)
+}
+
+/// deserialize a varsig from a struct
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    super::de::deserialize_struct(deserializer, DecodeLimits::default())
+}
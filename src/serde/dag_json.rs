@@ -0,0 +1,171 @@
+//! Serde (de)serialization of a [`Varsig`] as IPLD bytes: the DAG-JSON
+//! `{"/": {"bytes": "..."}}` convention in human-readable formats and a
+//! plain byte string in binary formats such as DAG-CBOR, so varsigs embedded
+//! in IPLD documents serialize the same as they do from js-ipld. The bytes
+//! are the sigil-tagged varsig; untagged varsigs are accepted when
+//! deserializing.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Envelope {
+//!     #[serde(with = "varsig::serde::dag_json")]
+//!     sig: Varsig,
+//! }
+//! ```
+use crate::Varsig;
+use core::fmt;
+use multibase::Base;
+use serde::{
+    de::{Error, MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+    Deserializer, Serializer,
+};
+
+/// the DAG-JSON reserved key
+const SLASH: &str = "/";
+
+/// the DAG-JSON bytes key
+const BYTES: &str = "bytes";
+
+/// serialize a varsig as IPLD bytes
+pub fn serialize<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let bytes = vs.to_tagged_bytes();
+    if serializer.is_human_readable() {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(SLASH, &Inner(&bytes))?;
+        map.end()
+    } else {
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+/// the `{"bytes": "..."}` inside the reserved key, using unpadded standard
+/// base64 as DAG-JSON requires
+struct Inner<'a>(&'a [u8]);
+
+impl serde::Serialize for Inner<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(BYTES, &Base::Base64.encode(self.0))?;
+        map.end()
+    }
+}
+
+/// deserialize a varsig from IPLD bytes
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = if deserializer.is_human_readable() {
+        deserializer.deserialize_map(LinkVisitor)?
+    } else {
+        deserializer.deserialize_bytes(BytesVisitor)?
+    };
+    Varsig::try_from(bytes.as_slice()).map_err(|e| D::Error::custom(e.to_string()))
+}
+
+/// expects a map with only the reserved key, holding the bytes
+struct LinkVisitor;
+
+impl<'de> Visitor<'de> for LinkVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a DAG-JSON bytes object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let key: String = map
+            .next_key()?
+            .ok_or_else(|| A::Error::missing_field(SLASH))?;
+        if key != SLASH {
+            return Err(A::Error::unknown_field(&key, &[SLASH]));
+        }
+        let bytes = map.next_value_seed(InnerSeed)?;
+        if map.next_key::<String>()?.is_some() {
+            return Err(A::Error::custom("DAG-JSON bytes object has extra keys"));
+        }
+        Ok(bytes)
+    }
+}
+
+/// expects `{"bytes": "..."}`
+struct InnerSeed;
+
+impl<'de> serde::de::DeserializeSeed<'de> for InnerSeed {
+    type Value = Vec<u8>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for InnerSeed {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map with a base64 \"bytes\" string")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let key: String = map
+            .next_key()?
+            .ok_or_else(|| A::Error::missing_field(BYTES))?;
+        if key != BYTES {
+            return Err(A::Error::unknown_field(&key, &[BYTES]));
+        }
+        let encoded: String = map.next_value()?;
+        if map.next_key::<String>()?.is_some() {
+            return Err(A::Error::custom("DAG-JSON bytes object has extra keys"));
+        }
+        // be liberal and accept padded base64 too
+        Base::Base64
+            .decode(encoded.trim_end_matches('='))
+            .map_err(|e| A::Error::custom(e.to_string()))
+    }
+}
+
+/// accepts any byte string
+pub(super) struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "varsig bytes")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(b) = seq.next_element()? {
+            v.push(b);
+        }
+        Ok(v)
+    }
+}
@@ -1,154 +1,324 @@
-use crate::{vs::SIGIL, Varsig};
+use crate::{
+    registry,
+    vs::{self, SIGIL},
+    CowVarsig, DecodeLimits, Varsig, VarsigVersion,
+};
 use core::fmt;
 use multicodec::Codec;
 use multiutil::{EncodedVarbytes, EncodedVaruint, Varbytes, Varuint};
 use serde::{
-    de::{Error, MapAccess, Visitor},
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
     Deserialize, Deserializer,
 };
+use std::borrow::Cow;
 
-/// Deserialize instance of [`crate::Varsig`]
+/// Deserialize instance of [`crate::Varsig`] within [`DecodeLimits::default`]
 impl<'de> Deserialize<'de> for Varsig {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        const FIELDS: &'static [&'static str] =
-            &["version", "codec", "encoding", "attributes", "signature"];
-
-        #[derive(Deserialize)]
-        #[serde(field_identifier, rename_all = "lowercase")]
-        enum Field {
-            Version,
-            Codec,
-            Encoding,
-            Attributes,
-            Signature,
-        }
+        DecodeLimits::default().deserialize(deserializer)
+    }
+}
+
+/// reject attributes or signature data over the limits
+fn check_limits<E: Error>(
+    limits: &DecodeLimits,
+    attributes: usize,
+    signature: usize,
+) -> Result<(), E> {
+    if attributes > limits.max_attributes {
+        return Err(E::custom(format!(
+            "{} varsig attributes, over the limit of {}",
+            attributes, limits.max_attributes
+        )));
+    }
+    if signature > limits.max_signature_len {
+        return Err(E::custom(format!(
+            "{} byte varsig signature, over the limit of {}",
+            signature, limits.max_signature_len
+        )));
+    }
+    Ok(())
+}
 
-        struct VarsigVisitor;
+/// the names of the fields of the struct form
+const FIELDS: &[&str] = &[
+    "version",
+    "codec",
+    "encoding",
+    "attributes",
+    "signature",
+    "segments",
+];
 
-        impl<'de> Visitor<'de> for VarsigVisitor {
-            type Value = Varsig;
+/// the fields of the struct form
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Version,
+    Codec,
+    Encoding,
+    Attributes,
+    Signature,
+    Segments,
+}
 
-            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "struct Varsig")
-            }
+/// visits the five-field struct form
+struct VarsigVisitor(DecodeLimits);
 
-            fn visit_map<V>(self, mut map: V) -> Result<Varsig, V::Error>
-            where
-                V: MapAccess<'de>,
-            {
-                let mut version = None;
-                let mut codec = None;
-                let mut msg_encoding = None;
-                let mut attributes = None;
-                let mut signature = None;
-                while let Some(key) = map.next_key()? {
-                    match key {
-                        Field::Version => {
-                            if version.is_some() {
-                                return Err(Error::duplicate_field("version"));
-                            }
-                            let v: u8 = map.next_value()?;
-                            version = Some(v);
-                        }
-                        Field::Codec => {
-                            if codec.is_some() {
-                                return Err(Error::duplicate_field("codec"));
-                            }
-                            let c: u64 = map.next_value()?;
-                            codec = Some(
-                                Codec::try_from(c)
-                                    .map_err(|_| Error::custom("invalid varsig codec"))?,
-                            );
-                        }
-                        Field::Encoding => {
-                            if msg_encoding.is_some() {
-                                return Err(Error::duplicate_field("encoding"));
-                            }
-                            let e: u64 = map.next_value()?;
-                            msg_encoding =
-                                Some(Codec::try_from(e).map_err(|_| {
-                                    Error::custom("invalid varsig payload encoding")
-                                })?);
-                        }
-                        Field::Attributes => {
-                            if attributes.is_some() {
-                                return Err(Error::duplicate_field("attributes"));
-                            }
-                            let cv: Vec<EncodedVaruint<u64>> = map.next_value()?;
-                            attributes = Some(cv);
-                        }
-                        Field::Signature => {
-                            if signature.is_some() {
-                                return Err(Error::duplicate_field("signature"));
-                            }
-                            let sig: EncodedVarbytes = map.next_value()?;
-                            signature = Some(sig);
-                        }
+impl<'de> Visitor<'de> for VarsigVisitor {
+    type Value = Varsig;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "struct Varsig")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Varsig, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut version = None;
+        let mut codec = None;
+        let mut msg_encoding = None;
+        let mut attributes = None;
+        let mut signature = None;
+        let mut segments = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Version => {
+                    if version.is_some() {
+                        return Err(Error::duplicate_field("version"));
                     }
+                    let v: u8 = map.next_value()?;
+                    version = Some(
+                        VarsigVersion::try_from(v)
+                            .map_err(|_| Error::custom("invalid varsig version"))?,
+                    );
                 }
-                let version = version.ok_or_else(|| Error::missing_field("version"))?;
-                let codec = codec.ok_or_else(|| Error::missing_field("codec"))?;
-                let msg_encoding = msg_encoding.ok_or_else(|| Error::missing_field("encoding"))?;
-                let attributes: Vec<u64> = attributes
-                    .ok_or_else(|| Error::missing_field("attributes"))?
-                    .iter()
-                    .map(|v| v.clone().to_inner().to_inner())
-                    .collect();
-                let signature = signature
-                    .ok_or_else(|| Error::missing_field("signature"))?
-                    .to_inner()
-                    .to_inner();
-                match codec {
-                    Codec::Ed25519Pub => Ok(Varsig::EdDSA {
-                        version,
-                        msg_encoding,
-                        signature,
-                    }),
-                    _ => Ok(Varsig::Unknown {
-                        version,
-                        codec,
-                        msg_encoding: Some(msg_encoding),
-                        attributes,
-                        signature,
-                    }),
+                Field::Codec => {
+                    if codec.is_some() {
+                        return Err(Error::duplicate_field("codec"));
+                    }
+                    let c: u64 = map.next_value()?;
+                    if Codec::try_from(c).is_err() && !registry::is_private_use(c) {
+                        return Err(Error::custom("invalid varsig codec"));
+                    }
+                    codec = Some(c);
+                }
+                Field::Encoding => {
+                    if msg_encoding.is_some() {
+                        return Err(Error::duplicate_field("encoding"));
+                    }
+                    let e: Option<u64> = map.next_value()?;
+                    msg_encoding = Some(
+                        e.map(Codec::try_from)
+                            .transpose()
+                            .map_err(|_| Error::custom("invalid varsig payload encoding"))?,
+                    );
+                }
+                Field::Segments => {
+                    if segments.is_some() {
+                        return Err(Error::duplicate_field("segments"));
+                    }
+                    let codes: Vec<u64> = map.next_value()?;
+                    segments = Some(
+                        codes
+                            .into_iter()
+                            .map(Codec::try_from)
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|_| Error::custom("invalid varsig encoding segment"))?,
+                    );
+                }
+                Field::Attributes => {
+                    if attributes.is_some() {
+                        return Err(Error::duplicate_field("attributes"));
+                    }
+                    let cv: Vec<EncodedVaruint<u64>> = map.next_value()?;
+                    attributes = Some(cv);
+                }
+                Field::Signature => {
+                    if signature.is_some() {
+                        return Err(Error::duplicate_field("signature"));
+                    }
+                    let sig: EncodedVarbytes = map.next_value()?;
+                    signature = Some(sig);
                 }
             }
         }
+        let version = version.ok_or_else(|| Error::missing_field("version"))?;
+        let codec = codec.ok_or_else(|| Error::missing_field("codec"))?;
+        let msg_encoding = msg_encoding.ok_or_else(|| Error::missing_field("encoding"))?;
+        let attributes: Vec<u64> = attributes
+            .ok_or_else(|| Error::missing_field("attributes"))?
+            .iter()
+            .map(|v| v.clone().to_inner().to_inner())
+            .collect();
+        let signature = signature
+            .ok_or_else(|| Error::missing_field("signature"))?
+            .to_inner()
+            .to_inner();
+        let segments = segments.unwrap_or_default();
+        if version == VarsigVersion::V2 && (msg_encoding.is_none() || !segments.is_empty()) {
+            return Err(Error::custom(
+                "v2 varsigs have exactly one payload encoding",
+            ));
+        }
+        check_limits(&self.0, attributes.len(), signature.len())?;
+        Ok(CowVarsig {
+            version,
+            code: codec,
+            msg_encoding,
+            encoding_segments: segments,
+            attributes,
+            signature: Cow::Owned(signature),
+        }
+        .into_owned())
+    }
+}
+
+/// deserialize the five-field struct form of a varsig in any format
+pub(crate) fn deserialize_struct<'de, D>(
+    deserializer: D,
+    limits: DecodeLimits,
+) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_struct("Varsig", FIELDS, VarsigVisitor(limits))
+}
 
+/// Deserialize instance of [`crate::Varsig`] within the limits, e.g.
+/// `DecodeLimits::new(4, 64).deserialize(&mut deserializer)`
+impl<'de> DeserializeSeed<'de> for DecodeLimits {
+    type Value = Varsig;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Varsig, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
         if deserializer.is_human_readable() {
-            deserializer.deserialize_struct("Varsig", FIELDS, VarsigVisitor)
+            deserializer.deserialize_any(LenientVisitor(self))
         } else {
-            let (sigil, version, codec, msg_encoding, attributes, signature): (
-                Codec,
-                Varuint<u8>,
-                Codec,
-                Codec,
-                Vec<Varuint<u64>>,
-                Varbytes,
-            ) = Deserialize::deserialize(deserializer)?;
-            if sigil != SIGIL {
-                return Err(Error::custom("deserialized sigil is not a Varsig sigil"));
-            }
-            let version = version.to_inner();
-            let attributes = attributes.iter().map(|v| v.clone().to_inner()).collect();
-            let signature = signature.to_inner();
-            match codec {
-                Codec::Ed25519Pub => Ok(Varsig::EdDSA {
-                    version,
-                    msg_encoding,
-                    signature,
-                }),
-                _ => Ok(Varsig::Unknown {
-                    version,
-                    codec,
-                    msg_encoding: Some(msg_encoding),
-                    attributes,
-                    signature,
-                }),
-            }
+            deserializer.deserialize_bytes(CompactVisitor(self))
+        }
+    }
+}
+
+/// visits any of the human-readable forms: the five-field struct, a
+/// multibase string, a bare hex string or an array of bytes, so varsigs
+/// from other implementations parse without a wrapper type
+struct LenientVisitor(DecodeLimits);
+
+impl<'de> Visitor<'de> for LenientVisitor {
+    type Value = Varsig;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a varsig struct, multibase or hex string, or array of bytes"
+        )
+    }
+
+    fn visit_map<V>(self, map: V) -> Result<Varsig, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        VarsigVisitor(self.0).visit_map(map)
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Varsig, E> {
+        let bytes = vs::decode_str(v).map_err(|e| E::custom(e.to_string()))?;
+        CompactVisitor(self.0).decode(&bytes)
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Varsig, E> {
+        CompactVisitor(self.0).decode(v)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Varsig, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(b) = seq.next_element::<u8>()? {
+            bytes.push(b);
+        }
+        CompactVisitor(self.0).decode(&bytes)
+    }
+}
+
+/// visits the compact form: the sigil-tagged varsig bytes, which
+/// non-self-describing formats such as bincode and postcard length prefix.
+/// self-describing formats may also hand over the six-element tuple older
+/// versions wrote.
+struct CompactVisitor(DecodeLimits);
+
+impl CompactVisitor {
+    fn decode<E: Error>(self, bytes: &[u8]) -> Result<Varsig, E> {
+        let (vs, rest) =
+            Varsig::try_decode_with_limits(bytes, self.0).map_err(|e| E::custom(e.to_string()))?;
+        if !rest.is_empty() {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        Ok(vs)
+    }
+}
+
+impl<'de> Visitor<'de> for CompactVisitor {
+    type Value = Varsig;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "varsig bytes")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Varsig, E> {
+        self.decode(v)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Varsig, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let sigil: Codec = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        if sigil != SIGIL {
+            return Err(Error::custom("deserialized sigil is not a Varsig sigil"));
+        }
+        let version: Varuint<u8> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        let codec: Varuint<u64> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(2, &self))?;
+        let msg_encoding: Codec = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(3, &self))?;
+        let attributes: Vec<Varuint<u64>> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(4, &self))?;
+        let signature: Varbytes = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(5, &self))?;
+        let version = VarsigVersion::try_from(version.to_inner())
+            .map_err(|_| Error::custom("invalid varsig version"))?;
+        let code = codec.to_inner();
+        if Codec::try_from(code).is_err() && !registry::is_private_use(code) {
+            return Err(Error::custom("invalid varsig codec"));
+        }
+        check_limits(&self.0, attributes.len(), signature.len())?;
+        let attributes = attributes.iter().map(|v| v.clone().to_inner()).collect();
+        let signature = signature.to_inner();
+        Ok(CowVarsig {
+            version,
+            code,
+            msg_encoding: Some(msg_encoding),
+            encoding_segments: Vec::default(),
+            attributes,
+            signature: Cow::Owned(signature),
         }
+        .into_owned())
     }
 }
@@ -2,12 +2,27 @@
 mod de;
 mod ser;
 
+/// Varsig fields as sigil-tagged bytes in every format
+pub mod as_bytes;
+
+/// Varsig fields as a multibase string in every format
+pub mod as_multibase;
+
+/// Varsig fields as the five-field struct in every format
+pub mod as_struct;
+
+/// Varsigs as IPLD bytes, following the DAG-JSON convention
+pub mod dag_json;
+
+/// Varsigs as a single multibase string in human-readable formats
+pub mod string;
+
 #[cfg(test)]
 mod tests {
-    use crate::{Builder, Varsig};
+    use crate::{Builder, RsaPadding, Varsig};
     use multibase::Base;
     use multicodec::Codec;
-    use serde_test::{assert_tokens, Configure, Token};
+    use serde_test::{assert_de_tokens, assert_tokens, Configure, Token};
 
     #[test]
     fn test_serde_compact() {
@@ -15,7 +30,21 @@ mod tests {
             .with_signature_bytes([0u8; 64].as_slice())
             .build();
 
-        assert_tokens(
+        // the sigil, version, codec, encoding, attribute count and signature
+        // length followed by the signature
+        const BYTES: [u8; 71] = {
+            let mut b = [0u8; 71];
+            b[0] = 0x34;
+            b[1] = 0x02;
+            b[2] = 0xed;
+            b[3] = 0x01;
+            b[6] = 64;
+            b
+        };
+        assert_tokens(&vs.clone().compact(), &[Token::Bytes(&BYTES)]);
+
+        // the tuple older versions wrote still deserializes
+        assert_de_tokens(
             &vs.compact(),
             &[
                 Token::Tuple { len: 6 },
@@ -49,6 +78,53 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_serde_retains_base() {
+        use crate::{EncodedVarsig, RetainedBase};
+
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        for base in [Base::Base58Btc, Base::Base32Lower, Base::Base16Lower] {
+            let s1 = serde_json::to_string(&vs.to_encoded(base)).unwrap();
+            let e: EncodedVarsig = serde_json::from_str(&s1).unwrap();
+            assert_eq!(e.base(), base);
+            let s2 = serde_json::to_string(&e).unwrap();
+            assert_eq!(s1, s2);
+            let s3 = serde_json::to_string(&e.with_base(Base::Base64Url)).unwrap();
+            assert!(s3.starts_with("\"u"));
+        }
+    }
+
+    #[test]
+    fn test_serde_lenient() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([7u8; 64].as_slice())
+            .build();
+        let tagged = vs.to_tagged_bytes();
+        let untagged: Vec<u8> = vs.clone().into();
+        let hex = hex::encode(&tagged);
+        let inputs = [
+            // the struct form
+            serde_json::to_string(&vs).unwrap(),
+            // multibase strings, tagged or not
+            format!("\"{}\"", vs.to_encoded(Base::Base58Btc)),
+            format!("\"{}\"", multibase::encode(Base::Base64Url, &untagged)),
+            format!("\"f{}\"", hex),
+            // bare hex in either case
+            format!("\"{}\"", hex),
+            format!("\"{}\"", hex.to_uppercase()),
+            // an array of bytes
+            serde_json::to_string(&tagged).unwrap(),
+        ];
+        for input in inputs {
+            let vs2: Varsig = serde_json::from_str(&input).unwrap();
+            assert_eq!(vs, vs2, "{}", input);
+        }
+        assert!(serde_json::from_str::<Varsig>("\"not a varsig\"").is_err());
+        assert!(serde_json::from_str::<Varsig>("42").is_err());
+    }
+
     #[test]
     fn test_serde_readable() {
         let vs = Builder::newv2(Codec::Ed25519Pub)
@@ -67,6 +143,7 @@ mod tests {
                 Token::BorrowedStr("codec"),
                 Token::U64(237_u64),
                 Token::BorrowedStr("encoding"),
+                Token::Some,
                 Token::U64(0_u64),
                 Token::BorrowedStr("attributes"),
                 Token::Seq { len: Some(0) },
@@ -88,6 +165,189 @@ mod tests {
         assert_eq!(vs1, vs2);
     }
 
+    #[test]
+    fn test_serde_limits() {
+        use crate::DecodeLimits;
+        use serde::de::DeserializeSeed;
+
+        let vs1 = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let s = serde_json::to_string(&vs1).unwrap();
+        let mut de = serde_json::Deserializer::from_str(&s);
+        let vs2 = DecodeLimits::new(0, 64).deserialize(&mut de).unwrap();
+        assert_eq!(vs1, vs2);
+        let mut de = serde_json::Deserializer::from_str(&s);
+        assert!(DecodeLimits::new(0, 63).deserialize(&mut de).is_err());
+
+        let v = serde_cbor::to_vec(&vs1).unwrap();
+        let mut de = serde_cbor::Deserializer::from_slice(&v);
+        assert!(DecodeLimits::new(0, 63).deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn test_serde_string() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Token {
+            #[serde(with = "crate::serde::string")]
+            sig: Varsig,
+        }
+
+        let _lock = crate::registry::lock_encodings();
+        let t1 = Token {
+            sig: Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build(),
+        };
+        // the preferred base encoding
+        let s = serde_json::to_string(&t1).unwrap();
+        let encoded = t1.sig.to_encoded(Base::Base64Url).to_string();
+        assert_eq!(s, format!("{{\"sig\":\"{}\"}}", encoded));
+        let t2: Token = serde_json::from_str(&s).unwrap();
+        assert_eq!(t1, t2);
+
+        // any base decodes
+        let s = format!("{{\"sig\":\"{}\"}}", t1.sig.to_encoded(Base::Base58Btc));
+        assert_eq!(t1, serde_json::from_str(&s).unwrap());
+
+        // binary formats keep the compact encoding
+        let v = serde_cbor::to_vec(&t1).unwrap();
+        assert_eq!(t1, serde_cbor::from_slice(&v).unwrap());
+    }
+
+    #[test]
+    fn test_serde_dag_json() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Envelope {
+            #[serde(with = "crate::serde::dag_json")]
+            sig: Varsig,
+        }
+
+        let e1 = Envelope {
+            sig: Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build(),
+        };
+        let s = serde_json::to_string(&e1).unwrap();
+        let bytes = Base::Base64.encode(e1.sig.to_tagged_bytes());
+        assert!(!bytes.ends_with('='));
+        assert_eq!(
+            s,
+            format!("{{\"sig\":{{\"/\":{{\"bytes\":\"{}\"}}}}}}", bytes)
+        );
+        let e2: Envelope = serde_json::from_str(&s).unwrap();
+        assert_eq!(e1, e2);
+
+        // extra keys make it something other than bytes
+        let s = format!("{{\"sig\":{{\"/\":{{\"bytes\":\"{}\",\"x\":1}}}}}}", bytes);
+        assert!(serde_json::from_str::<Envelope>(&s).is_err());
+
+        // binary formats get a byte string
+        let v = serde_ipld_dagcbor::to_vec(&e1).unwrap();
+        let tagged = e1.sig.to_tagged_bytes();
+        let mut cbor = vec![0x58, tagged.len() as u8];
+        cbor.extend(&tagged);
+        assert!(v.ends_with(&cbor));
+        let e2: Envelope = serde_ipld_dagcbor::from_slice(&v).unwrap();
+        assert_eq!(e1, e2);
+    }
+
+    #[test]
+    fn test_serde_helpers() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Fields {
+            #[serde(with = "crate::serde::as_multibase")]
+            a: Varsig,
+            #[serde(with = "crate::serde::as_bytes")]
+            b: Varsig,
+            #[serde(with = "crate::serde::as_struct")]
+            c: Varsig,
+        }
+
+        let _lock = crate::registry::lock_encodings();
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let f1 = Fields {
+            a: vs.clone(),
+            b: vs.clone(),
+            c: vs.clone(),
+        };
+
+        let j: serde_json::Value = serde_json::to_value(&f1).unwrap();
+        assert!(j["a"].is_string());
+        assert!(j["b"].is_array());
+        assert!(j["c"].is_object());
+        assert_eq!(f1, serde_json::from_value(j).unwrap());
+
+        // binary formats get the same representations
+        let v = serde_cbor::to_vec(&f1).unwrap();
+        let c: serde_cbor::Value = serde_cbor::from_slice(&v).unwrap();
+        let m = match c {
+            serde_cbor::Value::Map(m) => m,
+            _ => panic!("expected a map"),
+        };
+        let get = |k: &str| m.get(&serde_cbor::Value::Text(k.to_string())).unwrap();
+        assert!(matches!(get("a"), serde_cbor::Value::Text(_)));
+        assert_eq!(get("b"), &serde_cbor::Value::Bytes(vs.to_tagged_bytes()));
+        assert!(matches!(get("c"), serde_cbor::Value::Map(_)));
+        assert_eq!(f1, serde_cbor::from_slice(&v).unwrap());
+    }
+
+    #[test]
+    fn test_serde_bincode_postcard() {
+        for vs1 in variants() {
+            let v = bincode::serialize(&vs1).unwrap();
+            let vs2: Varsig = bincode::deserialize(&v).unwrap();
+            assert_eq!(vs1, vs2, "bincode: {:?}", vs1);
+
+            let v = postcard::to_allocvec(&vs1).unwrap();
+            let vs2: Varsig = postcard::from_bytes(&v).unwrap();
+            assert_eq!(vs1, vs2, "postcard: {:?}", vs1);
+        }
+
+        // the layout is the length followed by the tagged varsig bytes
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let v = postcard::to_allocvec(&vs).unwrap();
+        assert_eq!(v[0] as usize, vs.to_tagged_bytes().len());
+        assert_eq!(&v[1..], vs.to_tagged_bytes().as_slice());
+    }
+
+    #[test]
+    fn test_serde_v1_lossless() {
+        // bls12-381 g2 has no layout, so the v1 payload encoding stays in
+        // the signature data; and an EIP-191 varsig with a segment
+        let vs1 = Varsig::try_from([0x01, 0xeb, 0x01, 0x12, 0x71, 0xaa, 0xbb].as_slice()).unwrap();
+        let vs2 = Builder::newv1(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_encoding_segment(Codec::DagCbor)
+            .with_attributes(&[Codec::Keccak256.code()].to_vec())
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build();
+        for vs in [vs1, vs2] {
+            let bytes: Vec<u8> = vs.clone().into();
+
+            let s = serde_json::to_string(&vs).unwrap();
+            let j: Varsig = serde_json::from_str(&s).unwrap();
+            assert_eq!(vs, j, "{}", s);
+            let v: Vec<u8> = j.into();
+            assert_eq!(bytes, v);
+
+            let c = serde_cbor::to_vec(&vs).unwrap();
+            let c: Varsig = serde_cbor::from_slice(&c).unwrap();
+            assert_eq!(vs, c);
+            let v: Vec<u8> = c.into();
+            assert_eq!(bytes, v);
+        }
+
+        let s =
+            serde_json::to_string(&Varsig::try_from([0x01, 0xeb, 0x01, 0x12].as_slice()).unwrap())
+                .unwrap();
+        assert!(s.contains("\"encoding\":null"));
+    }
+
     #[test]
     fn test_serde_cbor() {
         let vs1 = Builder::newv2(Codec::Ed25519Pub)
@@ -99,14 +359,205 @@ mod tests {
     }
 
     #[test]
-    fn test_eip191_unknown() {
-        // this builds a Varsig::Unknown since we don't know about EIP-191
-        // encoded data that is hashed with Keccak256 and signed with secp256k1
+    fn test_private_use() {
+        let vs1 = Builder::newv2_private(0x300000)
+            .with_msg_encoding(Codec::Raw)
+            .with_attributes(&[1, 2, 3].to_vec())
+            .with_signature_bytes([0u8; 32].as_slice())
+            .build();
+        let s = serde_json::to_string(&vs1).unwrap();
+        let vs2: Varsig = serde_json::from_str(&s).unwrap();
+        assert_eq!(vs1, vs2);
+        let v = serde_cbor::to_vec(&vs1).unwrap();
+        let vs3: Varsig = serde_cbor::from_slice(v.as_slice()).unwrap();
+        assert_eq!(vs1, vs3);
+    }
+
+    // one of every variant and version so that the round trip matrix below
+    // covers all of them
+    fn variants() -> Vec<Varsig> {
+        vec![
+            Builder::newv1(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+            Builder::newv1(Codec::Ed448Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_signature_bytes([6u8; 114].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed448Pub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_signature_bytes([7u8; 114].as_slice())
+                .build(),
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes([3u8; 65].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes([4u8; 65].as_slice())
+                .build(),
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([8u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_hash_codec(Codec::Sha2256)
+                .with_low_s()
+                .with_rfc6979()
+                .with_signature_bytes([9u8; 65].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_hash_codec(Codec::Sha2256)
+                .with_der()
+                .with_signature_bytes([0x30u8; 70].as_slice())
+                .build(),
+            Builder::newv1(Codec::P256Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([10u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::P256Pub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_hash_codec(Codec::Sha2256)
+                .with_der()
+                .with_signature_bytes([0x30u8; 71].as_slice())
+                .build(),
+            Builder::newv2(Codec::RsaPub)
+                .with_msg_encoding(Codec::Raw)
+                .with_rsa_params(Codec::Sha2256, 256)
+                .with_signature_bytes([11u8; 256].as_slice())
+                .build(),
+            Builder::newv2(Codec::RsaPub)
+                .with_msg_encoding(Codec::Raw)
+                .with_rsa_params(Codec::Sha2512, 512)
+                .with_rsa_padding(RsaPadding::Pss { salt_len: 64 })
+                .with_signature_bytes([12u8; 512].as_slice())
+                .build(),
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_schnorr(None)
+                .with_signature_bytes([13u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_schnorr(Some(1))
+                .with_signature_bytes([14u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Sr25519Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_signing_context(b"substrate")
+                .with_signature_bytes([15u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_ed25519_context(b"zig")
+                .with_signature_bytes([16u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_ed25519_prehash(b"")
+                .with_signature_bytes([17u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Json)
+                .with_eip712_domain(&[18u8; 32])
+                .with_signature_bytes([19u8; 65].as_slice())
+                .build(),
+            Builder::newv2_private(0x300000)
+                .with_msg_encoding(Codec::Raw)
+                .with_attributes(&[1, 2, 3].to_vec())
+                .with_signature_bytes([5u8; 32].as_slice())
+                .build(),
+            Builder::newv2(Codec::default())
+                .with_msg_encoding(Codec::default())
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_variants_coverage() {
+        // a new variant fails to compile here until it is added to the list
+        // above
+        fn index(vs: &Varsig) -> usize {
+            match vs {
+                Varsig::Unknown { .. } => 0,
+                Varsig::EdDSA { .. } => 1,
+                Varsig::Ed25519ctx { .. } => 2,
+                Varsig::Ed25519ph { .. } => 3,
+                Varsig::Ed448 { .. } => 4,
+                Varsig::Es256K { .. } => 5,
+                Varsig::Es256 { .. } => 6,
+                Varsig::Eip191 { .. } => 7,
+                Varsig::Eip712 { .. } => 8,
+                Varsig::Schnorr { .. } => 9,
+                Varsig::Sr25519 { .. } => 10,
+                Varsig::Rsa { .. } => 11,
+                Varsig::PrivateUse { .. } => 12,
+            }
+        }
+        let mut seen = [false; 13];
+        for vs in variants() {
+            seen[index(&vs)] = true;
+        }
+        assert_eq!(seen, [true; 13]);
+
+        // and the flags, rsa paddings and optional attributes are all in it
+        let variants = variants();
+        assert!(variants.iter().any(|vs| vs.is_der()));
+        assert!(variants
+            .iter()
+            .any(|vs| matches!(vs, Varsig::Es256K { low_s: true, .. })));
+        assert!(variants.iter().any(|vs| matches!(
+            vs,
+            Varsig::Rsa {
+                padding: RsaPadding::Pss { .. },
+                ..
+            }
+        )));
+        assert!(variants
+            .iter()
+            .any(|vs| matches!(vs, Varsig::Schnorr { aux: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_roundtrip_matrix() {
+        for vs1 in variants() {
+            // human readable
+            let s = serde_json::to_string(&vs1).unwrap();
+            let vs2: Varsig = serde_json::from_str(&s).unwrap();
+            assert_eq!(vs1, vs2, "json: {:?}", vs1);
+
+            // compact
+            let v = serde_cbor::to_vec(&vs1).unwrap();
+            let vs2: Varsig = serde_cbor::from_slice(v.as_slice()).unwrap();
+            assert_eq!(vs1, vs2, "cbor: {:?}", vs1);
+
+            let v = serde_ipld_dagcbor::to_vec(&vs1).unwrap();
+            let vs2: Varsig = serde_ipld_dagcbor::from_slice(v.as_slice()).unwrap();
+            assert_eq!(vs1, vs2, "dag-cbor: {:?}", vs1);
+        }
+    }
+
+    #[test]
+    fn test_serde_eip191() {
+        // EIP-191 encoded data that is hashed with Keccak256 and signed with
+        // secp256k1 builds a Varsig::Eip191
         let vs1 = Builder::newv1(Codec::Secp256K1Pub)
             .with_msg_encoding(Codec::Eip191)
             .with_attributes(&[Codec::Keccak256.code()].to_vec())
             .with_signature_bytes([0u8; 64].as_slice())
             .build();
+        assert!(matches!(vs1, Varsig::Eip191 { .. }));
         let s = serde_json::to_string(&vs1).unwrap();
         let vs2: Varsig = serde_json::from_str(&s).unwrap();
         assert_eq!(vs1, vs2);
@@ -1,5 +1,5 @@
-use crate::{vs::SIGIL, Varsig};
-use multiutil::{CodecInfo, EncodedVaruint, Varbytes, Varuint};
+use crate::Varsig;
+use multiutil::{EncodedVaruint, Varbytes, Varuint};
 use serde::ser::{self, SerializeStruct};
 
 /// Serialize instance of [`crate::Varsig`]
@@ -9,30 +9,44 @@ impl ser::Serialize for Varsig {
         S: ser::Serializer,
     {
         if serializer.is_human_readable() {
-            let cv: Vec<EncodedVaruint<u64>> = self
-                .attributes()
-                .iter()
-                .map(|v| Varuint::<u64>::encoded_new(*v))
-                .collect();
-            let mut ss = serializer.serialize_struct("Varsig", 5)?;
-            ss.serialize_field("version", &self.version())?;
-            ss.serialize_field("codec", &self.codec().code())?;
-            ss.serialize_field("encoding", &self.msg_encoding().code())?;
-            ss.serialize_field("attributes", &cv)?;
-            ss.serialize_field("signature", &Varbytes::encoded_new(self.signature()))?;
-            ss.end()
+            serialize_struct(self, serializer)
         } else {
-            let cv: Vec<Varuint<u64>> = self.attributes().iter().map(|v| Varuint(*v)).collect();
-            let sig = Varbytes(self.signature());
-            (
-                SIGIL,
-                Varuint(self.version()),
-                self.codec(),
-                self.msg_encoding(),
-                cv,
-                sig,
-            )
-                .serialize(serializer)
+            // the sigil-tagged varsig bytes, which non-self-describing
+            // formats such as bincode and postcard length prefix
+            serializer.serialize_bytes(&self.to_tagged_bytes())
         }
     }
 }
+
+/// serialize the five-field struct form of a varsig in any format
+pub(crate) fn serialize_struct<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    let cv: Vec<EncodedVaruint<u64>> = vs
+        .attributes_slice()
+        .iter()
+        .map(|v| Varuint::<u64>::encoded_new(*v))
+        .collect();
+    // v1 headers may carry payload encoding segments after the payload
+    // encoding, which only get a field when there are some
+    let segments: Vec<u64> = vs.encoding_segments().iter().map(|c| c.code()).collect();
+    let len = if segments.is_empty() { 5 } else { 6 };
+    let mut ss = serializer.serialize_struct("Varsig", len)?;
+    ss.serialize_field("version", &u8::from(vs.version()))?;
+    ss.serialize_field("codec", &vs.code())?;
+    // null for v1 varsigs decoded without a known layout, whose payload
+    // encoding is part of the signature data
+    ss.serialize_field("encoding", &vs.header_msg_encoding().map(|c| c.code()))?;
+    ss.serialize_field("attributes", &cv)?;
+    ss.serialize_field(
+        "signature",
+        &Varbytes::encoded_new(vs.signature_bytes().to_vec()),
+    )?;
+    if segments.is_empty() {
+        ss.skip_field("segments")?;
+    } else {
+        ss.serialize_field("segments", &segments)?;
+    }
+    ss.end()
+}
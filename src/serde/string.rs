@@ -0,0 +1,42 @@
+//! Serde (de)serialization of a [`Varsig`] as a single multibase string in
+//! human-readable formats, for JSON APIs that want `"sig": "z3Ye1..."`
+//! rather than the five-field struct. Binary formats get the usual compact
+//! encoding.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Token {
+//!     #[serde(with = "varsig::serde::string")]
+//!     sig: Varsig,
+//! }
+//! ```
+use crate::{EncodedVarsig, Varsig};
+use multiutil::EncodingInfo;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// serialize a varsig as a multibase string in its preferred base encoding
+pub fn serialize<S>(vs: &Varsig, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.collect_str(&vs.to_encoded(vs.encoding()))
+    } else {
+        vs.serialize(serializer)
+    }
+}
+
+/// deserialize a varsig from a multibase string in any base encoding
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Varsig, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        let s = String::deserialize(deserializer)?;
+        EncodedVarsig::try_from(s.as_str())
+            .map(|e| e.to_inner())
+            .map_err(|e| D::Error::custom(e.to_string()))
+    } else {
+        Varsig::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{Builder, Varsig};
+use multicodec::Codec;
+
+/// the sha2-256 multicodec
+#[cfg(any(feature = "k256", feature = "p256"))]
+const SHA2_256: u64 = 0x12;
+
+/// sign msg with an Ed25519 key, producing an EdDSA varsig
+#[cfg(feature = "ed25519-dalek")]
+pub fn ed25519(key: &ed25519_dalek::SigningKey, msg: &[u8], msg_encoding: Codec) -> Varsig {
+    use ed25519_dalek::Signer;
+
+    let sig = key.sign(msg);
+    Builder::newv2(Codec::Ed25519Pub)
+        .with_msg_encoding(msg_encoding)
+        .with_signature_bytes(sig.to_bytes().as_slice())
+        .build()
+}
+
+/// sign the sha2-256 hash of msg with a secp256k1 key, producing an ES256K
+/// varsig with a 64 byte r || s signature. the nonce is deterministic (RFC
+/// 6979) and s is low.
+#[cfg(feature = "k256")]
+pub fn secp256k1(key: &k256::ecdsa::SigningKey, msg: &[u8], msg_encoding: Codec) -> Varsig {
+    use k256::ecdsa::{signature::Signer, Signature};
+
+    let sig: Signature = key.sign(msg);
+    Builder::newv2(Codec::Secp256K1Pub)
+        .with_msg_encoding(msg_encoding)
+        .with_attributes(&[SHA2_256].to_vec())
+        .with_signature_bytes(&sig.to_bytes())
+        .build()
+}
+
+/// sign a payload that was hashed elsewhere with a secp256k1 key, given its
+/// multihash, producing an ES256K varsig over the multihash's hash codec
+#[cfg(feature = "k256")]
+pub fn secp256k1_multihash(
+    key: &k256::ecdsa::SigningKey,
+    multihash: &[u8],
+    msg_encoding: Codec,
+) -> Result<Varsig, crate::Error> {
+    use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature};
+
+    let (hash, digest) = crate::payload::decode_multihash(multihash)?;
+    let sig: Signature = key
+        .sign_prehash(digest)
+        .map_err(|e| crate::Error::SigningFailed(e.to_string()))?;
+    Ok(Builder::newv2(Codec::Secp256K1Pub)
+        .with_msg_encoding(msg_encoding)
+        .with_hash_codec(hash)
+        .with_signature_bytes(&sig.to_bytes())
+        .build())
+}
+
+/// sign msg the way Ethereum's personal_sign does, producing an EIP-191
+/// varsig with a 65 byte r || s || v signature
+#[cfg(feature = "k256")]
+pub fn eip191(key: &k256::ecdsa::SigningKey, msg: &[u8]) -> Result<Varsig, crate::Error> {
+    use sha3::{Digest, Keccak256};
+
+    let prehash = Keccak256::digest(crate::payload::eip191(msg));
+    let (sig, recovery_id) = key
+        .sign_prehash_recoverable(&prehash)
+        .map_err(|e| crate::Error::SigningFailed(e.to_string()))?;
+    let mut signature = sig.to_bytes().to_vec();
+    signature.push(recovery_id.to_byte() + 27);
+    Ok(Builder::newv2(Codec::Secp256K1Pub)
+        .with_msg_encoding(Codec::Eip191)
+        .with_hash_codec(Codec::Keccak256)
+        .with_signature_bytes(signature.as_slice())
+        .build())
+}
+
+/// sign the sha2-256 hash of msg with a NIST P-256 key, producing an ES256
+/// varsig. the nonce is deterministic (RFC 6979).
+#[cfg(feature = "p256")]
+pub fn p256(key: &p256::ecdsa::SigningKey, msg: &[u8], msg_encoding: Codec) -> Varsig {
+    use p256::ecdsa::{signature::Signer, Signature};
+
+    let sig: Signature = key.sign(msg);
+    Builder::newv2(Codec::P256Pub)
+        .with_msg_encoding(msg_encoding)
+        .with_attributes(&[SHA2_256].to_vec())
+        .with_signature_bytes(&sig.to_bytes())
+        .build()
+}
+
+/// sign a payload that was hashed elsewhere with a NIST P-256 key, given its
+/// multihash, producing an ES256 varsig over the multihash's hash codec
+#[cfg(feature = "p256")]
+pub fn p256_multihash(
+    key: &p256::ecdsa::SigningKey,
+    multihash: &[u8],
+    msg_encoding: Codec,
+) -> Result<Varsig, crate::Error> {
+    use p256::ecdsa::{signature::hazmat::PrehashSigner, Signature};
+
+    let (hash, digest) = crate::payload::decode_multihash(multihash)?;
+    let sig: Signature = key
+        .sign_prehash(digest)
+        .map_err(|e| crate::Error::SigningFailed(e.to_string()))?;
+    Ok(Builder::newv2(Codec::P256Pub)
+        .with_msg_encoding(msg_encoding)
+        .with_hash_codec(hash)
+        .with_signature_bytes(&sig.to_bytes())
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "ed25519-dalek")]
+    #[test]
+    fn test_ed25519() {
+        let sk = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let vs = ed25519(&sk, b"for great justice", Codec::Raw);
+        assert!(matches!(vs, Varsig::EdDSA { .. }));
+        let pk = sk.verifying_key().to_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_secp256k1() {
+        let sk = k256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pk = sk.verifying_key().to_sec1_bytes();
+        let vs = secp256k1(&sk, b"for great justice", Codec::Raw);
+        assert!(matches!(vs, Varsig::Es256K { .. }));
+        // the experimental ecdsa flags aren't recorded by default
+        assert_eq!(vs.attributes(), [SHA2_256].to_vec());
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+
+        let vs = eip191(&sk, b"for great justice").unwrap();
+        assert!(matches!(vs, Varsig::Eip191 { .. }));
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+        assert_eq!(
+            vs.recover_public_key(b"for great justice").unwrap(),
+            *sk.verifying_key()
+        );
+    }
+
+    #[cfg(feature = "p256")]
+    #[test]
+    fn test_p256() {
+        use sha2::{Digest, Sha256};
+
+        let sk = p256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let vs = p256(&sk, b"for great justice", Codec::Raw);
+        assert!(matches!(vs, Varsig::Es256 { .. }));
+        assert_eq!(vs.attributes(), [SHA2_256].to_vec());
+        let pk = sk.verifying_key().to_sec1_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+
+        // the same signature over the sha2-256 multihash of the payload
+        let mut mh = vec![0x12, 0x20];
+        mh.extend_from_slice(&Sha256::digest(b"for great justice"));
+        let vs = p256_multihash(&sk, &mh, Codec::Raw).unwrap();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+        assert!(vs.verify_multihash(&pk, &mh).is_ok());
+    }
+}
@@ -0,0 +1,285 @@
+#[cfg(feature = "digest")]
+pub use crate::hash::HashCodec;
+use crate::{alg, telemetry, Builder, Varsig};
+use multicodec::Codec;
+#[cfg(feature = "digest")]
+use signature::{digest::Digest, DigestSigner, DigestVerifier};
+#[cfg(feature = "rand")]
+use signature::{rand_core::CryptoRngCore, RandomizedSigner};
+use signature::{Error, SignatureEncoding, Signer, Verifier};
+use std::marker::PhantomData;
+
+/// Varsigs are signatures in their own right, encoded as their varsig bytes,
+/// so they can be used wherever the RustCrypto traits expect a signature type
+impl SignatureEncoding for Varsig {
+    type Repr = Vec<u8>;
+}
+
+/// Wraps any RustCrypto signing key so that it implements `Signer<Varsig>`
+#[derive(Clone, Debug)]
+pub struct VarsigSigner<K, S> {
+    key: K,
+    codec: Codec,
+    msg_encoding: Codec,
+    hash: Option<Codec>,
+    _sig: PhantomData<fn() -> S>,
+}
+
+impl<K, S> VarsigSigner<K, S> {
+    /// wrap a signing key, producing v2 varsigs with the given key codec.
+    /// algorithms that hash the payload record the hash their RustCrypto
+    /// signers use by default, e.g. sha2-256 for ES256K and ES256.
+    pub fn new(key: K, codec: Codec) -> Self {
+        Self {
+            key,
+            codec,
+            msg_encoding: Codec::Raw,
+            hash: alg::info(codec).and_then(|a| a.default_hash),
+            _sig: PhantomData,
+        }
+    }
+
+    /// set the payload encoding recorded in the produced varsigs
+    pub fn with_msg_encoding(mut self, codec: Codec) -> Self {
+        self.msg_encoding = codec;
+        self
+    }
+
+    /// set the hash codec recorded in the produced varsigs, for keys whose
+    /// `Signer` impl hashes the payload with something other than the
+    /// default for their algorithm
+    pub fn with_hash_codec(mut self, codec: Codec) -> Self {
+        self.hash = Some(codec);
+        self
+    }
+
+    /// get the wrapped key
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+
+    /// build the varsig for a signature made with the given hash, rejecting
+    /// it if it doesn't fit the signing algorithm
+    fn build(&self, hash: Option<Codec>, signature: &[u8]) -> Result<Varsig, Error> {
+        let builder = Builder::newv2(self.codec).with_msg_encoding(self.msg_encoding);
+        let builder = match hash {
+            Some(hash) if self.codec == Codec::RsaPub => {
+                builder.with_rsa_params(hash, signature.len())
+            }
+            Some(hash) => builder.with_hash_codec(hash),
+            None => builder,
+        };
+        builder
+            .with_signature_bytes(signature)
+            .try_build()
+            .map_err(|_| Error::new())
+    }
+}
+
+impl<K, S> Signer<Varsig> for VarsigSigner<K, S>
+where
+    K: Signer<S>,
+    S: SignatureEncoding,
+{
+    fn try_sign(&self, msg: &[u8]) -> Result<Varsig, Error> {
+        let sig = self.key.try_sign(msg)?;
+        self.build(self.hash, sig.to_bytes().as_ref())
+    }
+}
+
+#[cfg(feature = "rand")]
+impl<K, S> RandomizedSigner<Varsig> for VarsigSigner<K, S>
+where
+    K: RandomizedSigner<S>,
+    S: SignatureEncoding,
+{
+    fn try_sign_with_rng(&self, rng: &mut impl CryptoRngCore, msg: &[u8]) -> Result<Varsig, Error> {
+        let sig = self.key.try_sign_with_rng(rng, msg)?;
+        self.build(self.hash, sig.to_bytes().as_ref())
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<K, S, D> DigestSigner<D, Varsig> for VarsigSigner<K, S>
+where
+    K: DigestSigner<D, S>,
+    S: SignatureEncoding,
+    D: Digest + HashCodec,
+{
+    fn try_sign_digest(&self, digest: D) -> Result<Varsig, Error> {
+        let sig = self.key.try_sign_digest(digest)?;
+        self.build(Some(D::CODEC), sig.to_bytes().as_ref())
+    }
+}
+
+/// Wraps any RustCrypto verifying key so that it implements
+/// `Verifier<Varsig>`. Varsigs with a different key codec are rejected.
+#[derive(Clone, Debug)]
+pub struct VarsigVerifier<K, S> {
+    key: K,
+    codec: Codec,
+    _sig: PhantomData<fn() -> S>,
+}
+
+impl<K, S> VarsigVerifier<K, S> {
+    /// wrap a verifying key for varsigs with the given key codec
+    pub fn new(key: K, codec: Codec) -> Self {
+        Self {
+            key,
+            codec,
+            _sig: PhantomData,
+        }
+    }
+
+    /// get the wrapped key
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+}
+
+impl<K, S> Verifier<Varsig> for VarsigVerifier<K, S>
+where
+    K: Verifier<S>,
+    S: for<'a> TryFrom<&'a [u8]>,
+{
+    fn verify(&self, msg: &[u8], vs: &Varsig) -> Result<(), Error> {
+        let timer = telemetry::VerifyTimer::start();
+        let result = if vs.code() != self.codec.code() {
+            Err(Error::new())
+        } else {
+            S::try_from(vs.as_ref())
+                .map_err(|_| Error::new())
+                .and_then(|sig| self.key.verify(msg, &sig))
+        };
+        timer.record(result.is_ok());
+        result
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<K, S, D> DigestVerifier<D, Varsig> for VarsigVerifier<K, S>
+where
+    K: DigestVerifier<D, S>,
+    S: for<'a> TryFrom<&'a [u8]>,
+    D: Digest + HashCodec,
+{
+    fn verify_digest(&self, digest: D, vs: &Varsig) -> Result<(), Error> {
+        let timer = telemetry::VerifyTimer::start();
+        // the hash codec is the first signature-specific attribute
+        let result = if vs.code() != self.codec.code()
+            || vs.attributes_slice().first() != Some(&D::CODEC.code())
+        {
+            Err(Error::new())
+        } else {
+            S::try_from(vs.as_ref())
+                .map_err(|_| Error::new())
+                .and_then(|sig| self.key.verify_digest(digest, &sig))
+        };
+        timer.record(result.is_ok());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+    #[test]
+    fn test_sign_verify() {
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let vk = sk.verifying_key();
+        let signer: VarsigSigner<SigningKey, Signature> =
+            VarsigSigner::new(sk, Codec::Ed25519Pub).with_msg_encoding(Codec::Raw);
+        let verifier: VarsigVerifier<VerifyingKey, Signature> =
+            VarsigVerifier::new(vk, Codec::Ed25519Pub);
+
+        let vs = signer.sign(b"for great justice");
+        assert!(matches!(vs, Varsig::EdDSA { .. }));
+        assert!(verifier.verify(b"for great justice", &vs).is_ok());
+        assert!(verifier.verify(b"move every zig", &vs).is_err());
+    }
+
+    #[test]
+    fn test_signature_encoding() {
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let signer: VarsigSigner<SigningKey, Signature> = VarsigSigner::new(sk, Codec::Ed25519Pub);
+        let vs = signer.sign(b"for great justice");
+        let bytes = vs.to_bytes();
+        assert_eq!(bytes.len(), vs.encoded_len());
+        assert_eq!(vs, Varsig::try_from(bytes.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_wrong_codec() {
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let vk = sk.verifying_key();
+        let signer: VarsigSigner<SigningKey, Signature> =
+            VarsigSigner::new(sk, Codec::Secp256K1Pub);
+        let verifier: VarsigVerifier<VerifyingKey, Signature> =
+            VarsigVerifier::new(vk, Codec::Ed25519Pub);
+        let vs = signer.sign(b"for great justice");
+        assert!(verifier.verify(b"for great justice", &vs).is_err());
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_digest_sign_verify() {
+        use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+        use sha3::Keccak256;
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let vk = *sk.verifying_key();
+        let signer: VarsigSigner<SigningKey, Signature> =
+            VarsigSigner::new(sk, Codec::Secp256K1Pub);
+        let verifier: VarsigVerifier<VerifyingKey, Signature> =
+            VarsigVerifier::new(vk, Codec::Secp256K1Pub);
+
+        let mut digest = Keccak256::new();
+        digest.update(b"for great ");
+        digest.update(b"justice");
+        let vs = signer.sign_digest(digest);
+        assert_eq!(vs.attributes(), vec![Codec::Keccak256.code()]);
+
+        let digest = Keccak256::new_with_prefix(b"for great justice");
+        assert!(verifier.verify_digest(digest, &vs).is_ok());
+        let digest = Keccak256::new_with_prefix(b"move every zig");
+        assert!(verifier.verify_digest(digest, &vs).is_err());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sign_with_rng() {
+        use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+        use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let vk = *sk.verifying_key();
+        let signer: VarsigSigner<SigningKey, Signature> =
+            VarsigSigner::new(sk, Codec::Secp256K1Pub);
+        let verifier: VarsigVerifier<VerifyingKey, Signature> =
+            VarsigVerifier::new(vk, Codec::Secp256K1Pub);
+
+        // the same seed gives the same signature
+        let vs1 = signer.sign_with_rng(&mut ChaCha20Rng::seed_from_u64(42), b"for great justice");
+        let vs2 = signer.sign_with_rng(&mut ChaCha20Rng::seed_from_u64(42), b"for great justice");
+        assert_eq!(vs1, vs2);
+        assert!(verifier.verify(b"for great justice", &vs1).is_ok());
+
+        // the varsig records the sha2-256 hash the key signed with
+        let vs = Varsig::try_from(vs1.to_bytes().as_slice()).unwrap();
+        assert!(matches!(
+            vs,
+            Varsig::Es256K {
+                hash: Codec::Sha2256,
+                ..
+            }
+        ));
+        #[cfg(feature = "k256")]
+        {
+            let pk = vk.to_sec1_bytes();
+            assert!(vs.verify(&pk, b"for great justice").is_ok());
+            assert!(vs.verify(&pk, b"move every zig").is_err());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{telemetry, Error, Varsig};
+use signature::Verifier;
+use ssh_key::{Certificate, Fingerprint, Signature};
+
+impl Varsig {
+    /// verify this varsig over msg using the key certified by an SSH
+    /// certificate. the certificate's validity window and its CA signature are
+    /// checked against the trusted CA fingerprints before the varsig is.
+    pub fn verify_ssh_certificate(
+        &self,
+        msg: &[u8],
+        cert: &Certificate,
+        ca_fingerprints: &[Fingerprint],
+    ) -> Result<(), Error> {
+        cert.validate(ca_fingerprints)?;
+        let sig = Signature::try_from(self)?;
+        let timer = telemetry::VerifyTimer::start();
+        let result = cert
+            .public_key()
+            .verify(msg, &sig)
+            .map_err(|_| Error::VerificationFailed);
+        timer.record(result.is_ok());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Builder;
+    use signature::Signer;
+    use ssh_key::{certificate, private::Ed25519Keypair, HashAlg, PrivateKey};
+
+    #[test]
+    fn test_verify_ssh_certificate() {
+        let ca = PrivateKey::from(Ed25519Keypair::from_seed(&[1u8; 32]));
+        let user = PrivateKey::from(Ed25519Keypair::from_seed(&[2u8; 32]));
+
+        let mut builder = certificate::Builder::new(
+            [0u8; 16],
+            user.public_key().key_data().clone(),
+            0,
+            i64::MAX as u64,
+        )
+        .unwrap();
+        builder.key_id("user").unwrap();
+        builder.all_principals_valid().unwrap();
+        let cert = builder.sign(&ca).unwrap();
+        let cas = [ca.public_key().fingerprint(HashAlg::Sha256)];
+
+        let sig: ssh_key::Signature = Signer::sign(&user, b"for great justice");
+        let vs = Builder::new_from_ssh_signature(&sig).unwrap().build();
+        assert!(vs
+            .verify_ssh_certificate(b"for great justice", &cert, &cas)
+            .is_ok());
+        assert!(vs
+            .verify_ssh_certificate(b"move every zig", &cert, &cas)
+            .is_err());
+
+        // an untrusted CA fails
+        let other = PrivateKey::from(Ed25519Keypair::from_seed(&[3u8; 32]));
+        let others = [other.public_key().fingerprint(HashAlg::Sha256)];
+        assert!(vs
+            .verify_ssh_certificate(b"for great justice", &cert, &others)
+            .is_err());
+    }
+}
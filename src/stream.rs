@@ -0,0 +1,115 @@
+use crate::{hash::HashCodec, Error, Varsig};
+use sha2::digest::{Digest, Update};
+use std::io;
+
+/// Verifies a varsig over a payload that is fed to it in pieces, so that
+/// large payloads never have to be held in memory. Create one with
+/// [`Varsig::verifier`], feed it the payload through [`Update`] or
+/// [`io::Write`] and finish with [`StreamVerifier::verify`].
+pub struct StreamVerifier<'a, D> {
+    vs: &'a Varsig,
+    hasher: D,
+}
+
+impl Varsig {
+    /// start verifying this varsig over a streamed payload, hashing it with
+    /// D. only algorithms that sign a hash of the payload can be streamed:
+    /// ES256K, ES256 and RSA, and D must be the hash named by the hash
+    /// attribute.
+    pub fn verifier<D: Digest + HashCodec>(&self) -> Result<StreamVerifier<'_, D>, Error> {
+        let hash = match self {
+            Varsig::Es256K { hash, .. } | Varsig::Es256 { hash, .. } | Varsig::Rsa { hash, .. } => {
+                *hash
+            }
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        };
+        if hash != D::CODEC {
+            return Err(Error::InvalidPayload(format!(
+                "{:?} digest for a varsig over {:?}",
+                D::CODEC,
+                hash
+            )));
+        }
+        Ok(StreamVerifier {
+            vs: self,
+            hasher: D::new(),
+        })
+    }
+}
+
+impl<D: Digest> Update for StreamVerifier<'_, D> {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.hasher, data);
+    }
+}
+
+impl<D: Digest> io::Write for StreamVerifier<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Update::update(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<D: Digest> StreamVerifier<'_, D> {
+    /// check the signature over everything fed in with the signer's public
+    /// key, in the same form [`Varsig::verify`] takes it
+    pub fn verify(self, public_key: &[u8]) -> Result<(), Error> {
+        self.vs.verify_digest(public_key, &self.hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Builder, Error};
+    use multicodec::Codec;
+    use sha2::Sha256;
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_stream_es256k() {
+        use std::io::Write;
+
+        let sk = k256::ecdsa::SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pk = sk.verifying_key().to_sec1_bytes();
+        let msg = vec![0x5a; 1 << 20];
+        let vs = crate::sign::secp256k1(&sk, &msg, Codec::Raw);
+
+        let mut verifier = vs.verifier::<Sha256>().unwrap();
+        for chunk in msg.chunks(4096) {
+            verifier.write_all(chunk).unwrap();
+        }
+        assert!(verifier.verify(&pk).is_ok());
+
+        let mut verifier = vs.verifier::<Sha256>().unwrap();
+        verifier.write_all(&msg[1..]).unwrap();
+        assert!(verifier.verify(&pk).is_err());
+    }
+
+    #[test]
+    fn test_not_streamable() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            vs.verifier::<Sha256>(),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_digest() {
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2384)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(vs.verifier::<sha2::Sha384>().is_ok());
+        assert!(matches!(
+            vs.verifier::<Sha256>(),
+            Err(Error::InvalidPayload(_))
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Error;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+/// a short, stable label for the kind of error
+#[cfg(feature = "metrics")]
+fn kind(e: &Error) -> &'static str {
+    match e {
+        Error::Fmt(_) => "fmt",
+        Error::Multibase(_) => "multibase",
+        Error::Multicodec(_) => "multicodec",
+        Error::Multiutil(_) => "multiutil",
+        Error::MissingSigil => "missing_sigil",
+        Error::InvalidVersion(_) => "invalid_version",
+        Error::Truncated { .. } => "truncated",
+        Error::InvalidField { .. } => "invalid_field",
+        Error::LimitExceeded { .. } => "limit_exceeded",
+        Error::UnsupportedAlgorithm(_) => "unsupported_algorithm",
+        _ => "other",
+    }
+}
+
+/// count a decode attempt and, if it failed, the kind of failure
+pub(crate) fn record_decode(err: Option<&Error>) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("varsig_decodes_total").increment(1);
+        if let Some(e) = err {
+            metrics::counter!("varsig_decode_failures_total", "kind" => kind(e)).increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = err;
+}
+
+/// Times a verification. it only reads the clock when the `metrics` feature
+/// is on, and is zero sized otherwise.
+pub(crate) struct VerifyTimer {
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+impl VerifyTimer {
+    /// start timing a verification
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    /// count the verification and record how long it took
+    pub(crate) fn record(self, ok: bool) {
+        #[cfg(feature = "metrics")]
+        {
+            let result = if ok { "ok" } else { "failed" };
+            metrics::counter!("varsig_verifications_total", "result" => result).increment(1);
+            metrics::histogram!("varsig_verification_seconds")
+                .record(self.start.elapsed().as_secs_f64());
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = ok;
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use crate::Varsig;
+    use metrics_util::debugging::DebuggingRecorder;
+
+    #[test]
+    fn test_decode_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let _ = Varsig::try_from([0x02, 0xed, 0x01, 0x00, 0x00, 0x00].as_slice());
+            let _ = Varsig::try_from([0x07].as_slice());
+        });
+        let names: Vec<String> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, _)| key.key().name().to_string())
+            .collect();
+        assert!(names.contains(&"varsig_decodes_total".to_string()));
+        assert!(names.contains(&"varsig_decode_failures_total".to_string()));
+    }
+}
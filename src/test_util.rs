@@ -0,0 +1,16 @@
+//! Helpers shared by the async tests
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// poll a future once and return its output. the futures in the tests are
+/// always ready (in-memory readers and writers, canned signers), so one poll
+/// is enough.
+pub(crate) fn ready<T>(fut: impl Future<Output = T>) -> T {
+    match pin!(fut).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(t) => t,
+        Poll::Pending => panic!("future not ready"),
+    }
+}
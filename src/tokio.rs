@@ -0,0 +1,288 @@
+//! Varsig frames over async byte streams.
+//!
+//! A v1 varsig without a fixed signature length runs to the end of its input
+//! so varsigs can't simply be written back to back. Each frame is instead the
+//! varuint length of the encoded varsig followed by the varsig itself.
+use crate::{Error, Varsig};
+use bytes::{Buf, BufMut, BytesMut};
+use multitrait::TryDecodeFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// the largest frame accepted by default; more than enough for a 4096 bit RSA
+/// signature and its header
+pub const DEFAULT_MAX_FRAME_LEN: usize = 4096;
+
+/// A [`tokio_util::codec`] codec for length-prefixed varsig frames, for use
+/// with `Framed`, `FramedRead` and `FramedWrite`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VarsigCodec {
+    max_frame_len: usize,
+}
+
+impl VarsigCodec {
+    /// create a codec accepting frames up to [`DEFAULT_MAX_FRAME_LEN`] bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// create a codec accepting frames up to max_frame_len bytes
+    pub fn with_max_frame_len(max_frame_len: usize) -> Self {
+        Self { max_frame_len }
+    }
+
+    /// the largest frame the codec accepts
+    pub fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+}
+
+impl Default for VarsigCodec {
+    fn default() -> Self {
+        Self::with_max_frame_len(DEFAULT_MAX_FRAME_LEN)
+    }
+}
+
+impl Decoder for VarsigCodec {
+    type Item = Varsig;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (len, rest) = match unsigned_varint::decode::usize(src) {
+            Ok(v) => v,
+            // wait for the rest of the length prefix
+            Err(unsigned_varint::decode::Error::Insufficient) => return Ok(None),
+            Err(e) => {
+                return Err(Error::InvalidField {
+                    field: "frame length",
+                    offset: 0,
+                    reason: e.to_string(),
+                })
+            }
+        };
+        if len > self.max_frame_len {
+            return Err(Error::FrameTooLarge {
+                len,
+                max: self.max_frame_len,
+            });
+        }
+        let prefix = src.len() - rest.len();
+        if rest.len() < len {
+            // make room for the rest of the frame so the next read gets it
+            src.reserve(prefix + len - src.len());
+            return Ok(None);
+        }
+        src.advance(prefix);
+        let frame = src.split_to(len);
+        decode_frame(&frame).map(Some)
+    }
+}
+
+impl Encoder<&Varsig> for VarsigCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: &Varsig, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let len = item.encoded_len();
+        if len > self.max_frame_len {
+            return Err(Error::FrameTooLarge {
+                len,
+                max: self.max_frame_len,
+            });
+        }
+        let mut tmp = unsigned_varint::encode::usize_buffer();
+        let prefix = unsigned_varint::encode::usize(len, &mut tmp);
+        dst.reserve(prefix.len() + len);
+        dst.put_slice(prefix);
+        let start = dst.len();
+        dst.resize(start + len, 0);
+        item.encode_into(&mut dst[start..])?;
+        Ok(())
+    }
+}
+
+impl Encoder<Varsig> for VarsigCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Varsig, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+/// read one length-prefixed varsig frame, returning None if the stream ends
+/// cleanly before the frame starts
+pub async fn read_varsig<R>(r: &mut R, max_frame_len: usize) -> Result<Option<Varsig>, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut prefix = unsigned_varint::encode::usize_buffer();
+    let mut i = 0;
+    let len = loop {
+        let b = match r.read_u8().await {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && i == 0 => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if i == prefix.len() {
+            return Err(Error::InvalidField {
+                field: "frame length",
+                offset: 0,
+                reason: "varuint overflow".to_string(),
+            });
+        }
+        prefix[i] = b;
+        i += 1;
+        if b & 0x80 == 0 {
+            break unsigned_varint::decode::usize(&prefix[..i])
+                .map_err(|e| Error::InvalidField {
+                    field: "frame length",
+                    offset: 0,
+                    reason: e.to_string(),
+                })?
+                .0;
+        }
+    };
+    if len > max_frame_len {
+        return Err(Error::FrameTooLarge {
+            len,
+            max: max_frame_len,
+        });
+    }
+    let mut frame = vec![0; len];
+    r.read_exact(&mut frame).await?;
+    decode_frame(&frame).map(Some)
+}
+
+/// decode the varsig in a frame. the frame length covers exactly one varsig,
+/// so anything left over means the frame is corrupt.
+fn decode_frame(frame: &[u8]) -> Result<Varsig, Error> {
+    let (vs, rest) = Varsig::try_decode_from(frame)?;
+    if !rest.is_empty() {
+        return Err(Error::TrailingBytes(rest.len()));
+    }
+    Ok(vs)
+}
+
+/// write one length-prefixed varsig frame, returning the number of bytes
+/// written
+pub async fn write_varsig<W>(w: &mut W, vs: &Varsig) -> Result<usize, Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::new();
+    VarsigCodec::with_max_frame_len(usize::MAX).encode(vs, &mut buf)?;
+    w.write_all(&buf).await?;
+    Ok(buf.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::ready, Builder};
+    use multicodec::Codec;
+
+    fn varsigs() -> Vec<Varsig> {
+        vec![
+            // a v1 varsig with no fixed signature length runs to the end of
+            // its frame
+            Builder::newv1(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([1u8; 65].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let mut codec = VarsigCodec::new();
+        let mut buf = BytesMut::new();
+        for vs in varsigs() {
+            codec.encode(vs, &mut buf).unwrap();
+        }
+        for vs in varsigs() {
+            assert_eq!(codec.decode(&mut buf).unwrap(), Some(vs));
+        }
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_codec_partial() {
+        let vs = varsigs().remove(1);
+        let mut codec = VarsigCodec::new();
+        let mut full = BytesMut::new();
+        codec.encode(&vs, &mut full).unwrap();
+        let mut buf = BytesMut::new();
+        for b in &full[..full.len() - 1] {
+            buf.put_u8(*b);
+            assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        }
+        buf.put_u8(full[full.len() - 1]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(vs));
+    }
+
+    #[test]
+    fn test_codec_too_large() {
+        let mut codec = VarsigCodec::with_max_frame_len(16);
+        let mut buf = BytesMut::new();
+        assert!(matches!(
+            codec.encode(varsigs().remove(1), &mut buf),
+            Err(Error::FrameTooLarge { max: 16, .. })
+        ));
+        // a hostile length prefix is rejected before anything is buffered
+        let mut buf = BytesMut::from(&[0xff, 0xff, 0xff, 0x0f][..]);
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(Error::FrameTooLarge { max: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn test_trailing_bytes() {
+        // an ed25519 signature has a fixed length, so the extra byte in the
+        // frame is left over after the varsig
+        let mut v: Vec<u8> = varsigs().remove(1).into();
+        v.push(0);
+        let mut frame = BytesMut::new();
+        frame.put_u8(v.len() as u8);
+        frame.put_slice(&v);
+        let r = frame.to_vec();
+        assert!(matches!(
+            VarsigCodec::new().decode(&mut frame),
+            Err(Error::TrailingBytes(1))
+        ));
+        assert!(matches!(
+            ready(read_varsig(&mut r.as_slice(), DEFAULT_MAX_FRAME_LEN)),
+            Err(Error::TrailingBytes(1))
+        ));
+    }
+
+    #[test]
+    fn test_read_write() {
+        let mut out = Vec::default();
+        for vs in varsigs() {
+            ready(write_varsig(&mut out, &vs)).unwrap();
+        }
+        let mut r = out.as_slice();
+        for vs in varsigs() {
+            let read = ready(read_varsig(&mut r, DEFAULT_MAX_FRAME_LEN)).unwrap();
+            assert_eq!(read, Some(vs));
+        }
+        assert!(ready(read_varsig(&mut r, DEFAULT_MAX_FRAME_LEN))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let mut out = Vec::default();
+        ready(write_varsig(&mut out, &varsigs()[1])).unwrap();
+        let mut r = &out[..out.len() - 1];
+        assert!(matches!(
+            ready(read_varsig(&mut r, DEFAULT_MAX_FRAME_LEN)),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{cow, telemetry, CowVarsig, DecodeLimits, Error, Varsig, VarsigVersion};
+use multicodec::Codec;
+use multitrait::TryDecodeFrom;
+use multiutil::Varuint;
+use std::borrow::Cow;
+
+/// A varsig parsed in place: the attributes and signature are borrowed from
+/// the input and nothing is allocated until [`VarsigRef::to_owned`]. Use it
+/// to inspect large numbers of varsigs cheaply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VarsigRef<'a> {
+    pub(crate) version: VarsigVersion,
+    pub(crate) code: u64,
+    pub(crate) msg_encoding: Option<Codec>,
+    pub(crate) encoding_segment: Option<Codec>,
+    pub(crate) attributes: Attributes<'a>,
+    pub(crate) signature: &'a [u8],
+}
+
+impl<'a> VarsigRef<'a> {
+    /// parse a varsig in place, rejecting it if it is over the limits
+    pub fn try_decode_with_limits(
+        bytes: &'a [u8],
+        limits: DecodeLimits,
+    ) -> Result<(Self, &'a [u8]), Error> {
+        let result = cow::decode(bytes, limits);
+        telemetry::record_decode(result.as_ref().err());
+        result
+    }
+
+    /// get the version
+    pub fn version(&self) -> VarsigVersion {
+        self.version
+    }
+
+    /// get the signing codec value, including private use values
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    /// get the signing codec, if it is not a private use codec
+    pub fn codec(&self) -> Option<Codec> {
+        Codec::try_from(self.code).ok()
+    }
+
+    /// get the payload encoding, if the header records one
+    pub fn msg_encoding(&self) -> Option<Codec> {
+        self.msg_encoding
+    }
+
+    /// get the payload encoding segments that follow the payload encoding in
+    /// v1 headers
+    pub fn encoding_segments(&self) -> impl Iterator<Item = Codec> {
+        self.encoding_segment.into_iter()
+    }
+
+    /// iterate over the signature-specific attributes
+    pub fn attributes(&self) -> Attributes<'a> {
+        self.attributes
+    }
+
+    /// get the signature data
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    /// copy into an owned [`Varsig`]
+    pub fn to_owned(self) -> Varsig {
+        CowVarsig::from(self).into_owned()
+    }
+}
+
+impl<'a> From<VarsigRef<'a>> for CowVarsig<'a> {
+    fn from(vr: VarsigRef<'a>) -> Self {
+        let mut attributes = Vec::with_capacity(vr.attributes.len());
+        attributes.extend(vr.attributes);
+        Self {
+            version: vr.version,
+            code: vr.code,
+            msg_encoding: vr.msg_encoding,
+            encoding_segments: vr.encoding_segment.into_iter().collect(),
+            attributes,
+            signature: Cow::Borrowed(vr.signature),
+        }
+    }
+}
+
+impl<'a> TryDecodeFrom<'a> for VarsigRef<'a> {
+    type Error = Error;
+
+    fn try_decode_from(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
+        Self::try_decode_with_limits(bytes, DecodeLimits::default())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for VarsigRef<'a> {
+    type Error = Error;
+
+    fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
+        let (vr, _) = Self::try_decode_from(s)?;
+        Ok(vr)
+    }
+}
+
+/// Iterates over the signature-specific attributes of a [`VarsigRef`],
+/// decoding them from the borrowed header bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attributes<'a> {
+    remaining: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> Attributes<'a> {
+    /// the decoder has already checked that bytes holds count varuints
+    pub(crate) fn new(count: usize, bytes: &'a [u8]) -> Self {
+        Self {
+            remaining: count,
+            bytes,
+        }
+    }
+}
+
+impl Iterator for Attributes<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (attribute, ptr) = Varuint::<u64>::try_decode_from(self.bytes).ok()?;
+        self.remaining -= 1;
+        self.bytes = ptr;
+        Some(attribute.to_inner())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Attributes<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn test_varsig_ref() {
+        let vs = Builder::newv2(Codec::RsaPub)
+            .with_msg_encoding(Codec::DagCbor)
+            .with_attributes(&[0x12, 256, 32].to_vec())
+            .with_signature_bytes([1u8; 256].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let (vr, rest) = VarsigRef::try_decode_from(v.as_slice()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(vr.version(), VarsigVersion::V2);
+        assert_eq!(vr.codec(), Some(Codec::RsaPub));
+        assert_eq!(vr.msg_encoding(), Some(Codec::DagCbor));
+        assert_eq!(vr.attributes().len(), 3);
+        assert_eq!(
+            vr.attributes().collect::<Vec<_>>(),
+            [0x12, 256, 32].to_vec()
+        );
+        // the signature is borrowed from the input
+        assert_eq!(vr.signature().as_ptr(), v[v.len() - 256..].as_ptr());
+        assert_eq!(vr.to_owned(), vs);
+    }
+
+    #[test]
+    fn test_varsig_ref_v1() {
+        let vs = Builder::newv1(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_encoding_segment(Codec::DagCbor)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes([1u8; 65].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let vr = VarsigRef::try_from(v.as_slice()).unwrap();
+        assert_eq!(vr.msg_encoding(), Some(Codec::Eip191));
+        assert_eq!(vr.encoding_segments().collect::<Vec<_>>(), [Codec::DagCbor]);
+        assert_eq!(
+            vr.attributes().collect::<Vec<_>>(),
+            [Codec::Keccak256.code()]
+        );
+        assert_eq!(vr.to_owned(), vs);
+    }
+}
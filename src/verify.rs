@@ -0,0 +1,864 @@
+use crate::{telemetry, Error, Varsig};
+
+impl Varsig {
+    /// verify this varsig over msg with the signer's public key. which
+    /// algorithms are supported depends on the enabled crypto backend
+    /// features:
+    ///
+    /// * `ed25519-dalek`: EdDSA, with the 32 byte public key
+    /// * `k256`: ES256K over sha2-256 or Keccak256, EIP-191 and EIP-712 (msg
+    ///   is the hashStruct of the typed data message), with a SEC1 encoded
+    ///   public key
+    /// * `p256`: ES256 over sha2-256, with a SEC1 encoded public key
+    /// * `rsa`: RSA with either padding over sha2-256, sha2-384 or sha2-512,
+    ///   with a PKCS #1 DER encoded public key
+    /// * `openssl`: ES256 over sha2-256, sha2-384 or sha2-512, ES256K over
+    ///   sha2-256 and RSA as above, through OpenSSL for deployments that must
+    ///   use a FIPS validated provider. these take the place of the pure Rust
+    ///   backends when both are enabled.
+    /// * `aws-lc-rs`: EdDSA, ES256K over sha2-256 and ES256 over sha2-256 or
+    ///   sha2-384 through aws-lc, with the same key formats except that
+    ///   ECDSA keys must be uncompressed. these take the place of the pure
+    ///   Rust backends for the algorithms they cover, and give way to
+    ///   `openssl` when both are enabled.
+    ///
+    /// anything else fails with [`Error::UnsupportedAlgorithm`]
+    pub fn verify(&self, public_key: &[u8], msg: &[u8]) -> Result<(), Error> {
+        #[cfg(not(any(
+            feature = "ed25519-dalek",
+            feature = "k256",
+            feature = "p256",
+            feature = "rsa",
+            feature = "openssl",
+            feature = "aws-lc-rs"
+        )))]
+        let _ = (public_key, msg);
+        let timer = telemetry::VerifyTimer::start();
+        let result = match self {
+            #[cfg(feature = "aws-lc-rs")]
+            Varsig::EdDSA { signature, .. } => {
+                aws_lc::verify(&aws_lc::ED25519, public_key, msg, signature)
+            }
+            #[cfg(all(feature = "ed25519-dalek", not(feature = "aws-lc-rs")))]
+            Varsig::EdDSA { signature, .. } => eddsa::verify(public_key, msg, signature),
+            #[cfg(feature = "openssl")]
+            Varsig::Es256K {
+                hash,
+                der,
+                signature,
+                ..
+            } if hash.code() == SHA2_256 => ossl::verify_ecdsa(
+                ossl::Curve::Secp256k1,
+                *hash,
+                public_key,
+                msg,
+                signature,
+                *der,
+            ),
+            #[cfg(all(feature = "aws-lc-rs", not(feature = "openssl")))]
+            Varsig::Es256K {
+                hash,
+                der,
+                signature,
+                ..
+            } if hash.code() == SHA2_256 => aws_lc::verify_ecdsa(
+                aws_lc::Curve::Secp256k1,
+                *hash,
+                public_key,
+                msg,
+                signature,
+                *der,
+            ),
+            #[cfg(feature = "k256")]
+            Varsig::Es256K { .. } | Varsig::Eip191 { .. } | Varsig::Eip712 { .. } => {
+                es256k::verify(self, public_key, msg)
+            }
+            #[cfg(feature = "openssl")]
+            Varsig::Es256 {
+                hash,
+                der,
+                signature,
+                ..
+            } => ossl::verify_ecdsa(ossl::Curve::P256, *hash, public_key, msg, signature, *der),
+            #[cfg(all(feature = "aws-lc-rs", not(feature = "openssl")))]
+            Varsig::Es256 {
+                hash,
+                der,
+                signature,
+                ..
+            } if aws_lc::is_supported(*hash) => {
+                aws_lc::verify_ecdsa(aws_lc::Curve::P256, *hash, public_key, msg, signature, *der)
+            }
+            #[cfg(all(feature = "p256", not(feature = "openssl")))]
+            Varsig::Es256 {
+                hash,
+                der,
+                signature,
+                ..
+            } => es256::verify(*hash, public_key, msg, signature, *der),
+            #[cfg(feature = "openssl")]
+            Varsig::Rsa {
+                hash,
+                padding,
+                signature,
+                ..
+            } => ossl::verify_rsa(*hash, *padding, public_key, msg, signature),
+            #[cfg(all(feature = "rsa", not(feature = "openssl")))]
+            Varsig::Rsa {
+                hash,
+                padding,
+                signature,
+                ..
+            } => rs::verify(*hash, *padding, public_key, msg, signature),
+            _ => Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        };
+        // only count attempts that reached a backend
+        if !matches!(result, Err(Error::UnsupportedAlgorithm(_))) {
+            timer.record(result.is_ok());
+        }
+        result
+    }
+
+    /// verify this EdDSA varsig over msg with an already parsed Ed25519 key
+    #[cfg(feature = "ed25519-dalek")]
+    pub fn verify_dalek(&self, key: &ed25519_dalek::VerifyingKey, msg: &[u8]) -> Result<(), Error> {
+        let timer = telemetry::VerifyTimer::start();
+        let result = match self {
+            Varsig::EdDSA { signature, .. } => eddsa::verify_key(key, msg, signature),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        };
+        timer.record(result.is_ok());
+        result
+    }
+
+    /// verify this varsig over a payload that was hashed elsewhere, given its
+    /// multihash. the multihash's hash codec must match the varsig's hash
+    /// attribute. only ES256K, ES256 and RSA sign a plain hash of the payload
+    /// so only they can be verified this way.
+    pub fn verify_multihash(&self, public_key: &[u8], multihash: &[u8]) -> Result<(), Error> {
+        let (codec, digest) = crate::payload::decode_multihash(multihash)?;
+        match self {
+            Varsig::Es256K { hash, .. } | Varsig::Es256 { hash, .. } | Varsig::Rsa { hash, .. } => {
+                if *hash != codec {
+                    return Err(Error::InvalidPayload(format!(
+                        "{:?} multihash for a varsig over {:?}",
+                        codec, hash
+                    )));
+                }
+            }
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        }
+        self.verify_digest(public_key, digest)
+    }
+
+    /// verify this varsig over the digest of the payload, hashed with the
+    /// varsig's hash attribute
+    pub(crate) fn verify_digest(&self, public_key: &[u8], digest: &[u8]) -> Result<(), Error> {
+        #[cfg(not(any(
+            feature = "k256",
+            feature = "p256",
+            feature = "rsa",
+            feature = "openssl"
+        )))]
+        let _ = (public_key, digest);
+        let timer = telemetry::VerifyTimer::start();
+        let result = match self {
+            #[cfg(feature = "openssl")]
+            Varsig::Es256K { der, signature, .. } => ossl::verify_ecdsa_prehash(
+                ossl::Curve::Secp256k1,
+                public_key,
+                digest,
+                signature,
+                *der,
+            ),
+            #[cfg(all(feature = "k256", not(feature = "openssl")))]
+            Varsig::Es256K { der, signature, .. } => {
+                es256k::verify_prehash(public_key, digest, signature, *der)
+            }
+            #[cfg(feature = "openssl")]
+            Varsig::Es256 { der, signature, .. } => {
+                ossl::verify_ecdsa_prehash(ossl::Curve::P256, public_key, digest, signature, *der)
+            }
+            #[cfg(feature = "openssl")]
+            Varsig::Rsa {
+                hash,
+                padding,
+                signature,
+                ..
+            } => ossl::verify_rsa_prehash(*hash, *padding, public_key, digest, signature),
+            #[cfg(all(feature = "p256", not(feature = "openssl")))]
+            Varsig::Es256 {
+                hash,
+                der,
+                signature,
+                ..
+            } => es256::verify_prehash(*hash, public_key, digest, signature, *der),
+            #[cfg(all(feature = "rsa", not(feature = "openssl")))]
+            Varsig::Rsa {
+                hash,
+                padding,
+                signature,
+                ..
+            } => rs::verify_prehash(*hash, *padding, public_key, digest, signature),
+            _ => Err(Error::UnsupportedAlgorithm(format!("{:?}", self))),
+        };
+        if !matches!(result, Err(Error::UnsupportedAlgorithm(_))) {
+            timer.record(result.is_ok());
+        }
+        result
+    }
+}
+
+/// the sha2-256 multicodec
+#[cfg(any(
+    feature = "k256",
+    feature = "p256",
+    feature = "rsa",
+    feature = "openssl",
+    feature = "aws-lc-rs"
+))]
+const SHA2_256: u64 = 0x12;
+
+#[cfg(feature = "ed25519-dalek")]
+mod eddsa {
+    use crate::Error;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    #[cfg(not(feature = "aws-lc-rs"))]
+    pub(super) fn verify(public_key: &[u8], msg: &[u8], signature: &[u8]) -> Result<(), Error> {
+        let key = public_key
+            .try_into()
+            .ok()
+            .and_then(|pk| VerifyingKey::from_bytes(pk).ok())
+            .ok_or_else(|| Error::InvalidPublicKey("expected an Ed25519 public key".into()))?;
+        verify_key(&key, msg, signature)
+    }
+
+    pub(super) fn verify_key(
+        key: &VerifyingKey,
+        msg: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let sig = Signature::from_slice(signature).map_err(|_| Error::VerificationFailed)?;
+        key.verify(msg, &sig).map_err(|_| Error::VerificationFailed)
+    }
+}
+
+#[cfg(feature = "k256")]
+mod es256k {
+    use super::SHA2_256;
+    use crate::{Error, Varsig};
+    use k256::ecdsa::{
+        signature::{hazmat::PrehashVerifier, Verifier},
+        Signature, VerifyingKey,
+    };
+
+    fn parse(
+        public_key: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(VerifyingKey, Signature), Error> {
+        let key = VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|e| Error::InvalidPublicKey(e.to_string()))?;
+        let sig = if der {
+            Signature::from_der(signature)
+        } else {
+            let rs = crate::vs::raw_ecdsa(signature).ok_or(Error::VerificationFailed)?;
+            Signature::from_slice(rs)
+        }
+        .map_err(|_| Error::VerificationFailed)?;
+        Ok((key, sig))
+    }
+
+    pub(super) fn verify(vs: &Varsig, public_key: &[u8], msg: &[u8]) -> Result<(), Error> {
+        let (key, sig) = parse(public_key, vs.as_ref(), vs.is_der())?;
+        let result = match vs {
+            Varsig::Es256K { hash, .. } if hash.code() == SHA2_256 => key.verify(msg, &sig),
+            _ => key.verify_prehash(&vs.keccak_prehash(msg)?, &sig),
+        };
+        result.map_err(|_| Error::VerificationFailed)
+    }
+
+    /// verify a signature over an already hashed payload
+    #[cfg(not(feature = "openssl"))]
+    pub(super) fn verify_prehash(
+        public_key: &[u8],
+        prehash: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(), Error> {
+        let (key, sig) = parse(public_key, signature, der)?;
+        key.verify_prehash(prehash, &sig)
+            .map_err(|_| Error::VerificationFailed)
+    }
+}
+
+#[cfg(all(feature = "p256", not(feature = "openssl")))]
+mod es256 {
+    use super::SHA2_256;
+    use crate::Error;
+    use multicodec::Codec;
+    use p256::ecdsa::{
+        signature::{hazmat::PrehashVerifier, Verifier},
+        Signature, VerifyingKey,
+    };
+
+    fn parse(
+        hash: Codec,
+        public_key: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(VerifyingKey, Signature), Error> {
+        if hash.code() != SHA2_256 {
+            return Err(Error::UnsupportedAlgorithm(format!(
+                "ES256 with {:?}",
+                hash
+            )));
+        }
+        let key = VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|e| Error::InvalidPublicKey(e.to_string()))?;
+        let sig = if der {
+            Signature::from_der(signature)
+        } else {
+            Signature::from_slice(signature)
+        }
+        .map_err(|_| Error::VerificationFailed)?;
+        Ok((key, sig))
+    }
+
+    pub(super) fn verify(
+        hash: Codec,
+        public_key: &[u8],
+        msg: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(), Error> {
+        let (key, sig) = parse(hash, public_key, signature, der)?;
+        key.verify(msg, &sig).map_err(|_| Error::VerificationFailed)
+    }
+
+    /// verify a signature over an already hashed payload
+    pub(super) fn verify_prehash(
+        hash: Codec,
+        public_key: &[u8],
+        prehash: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(), Error> {
+        let (key, sig) = parse(hash, public_key, signature, der)?;
+        key.verify_prehash(prehash, &sig)
+            .map_err(|_| Error::VerificationFailed)
+    }
+}
+
+#[cfg(all(feature = "rsa", not(feature = "openssl")))]
+mod rs {
+    use super::SHA2_256;
+    use crate::{Error, RsaPadding};
+    use multicodec::Codec;
+    use rsa::{
+        pkcs1::DecodeRsaPublicKey, pkcs8::der::oid::AssociatedOid, Pkcs1v15Sign, Pss, RsaPublicKey,
+    };
+    use sha2::{digest::DynDigest, Digest, Sha256, Sha384, Sha512};
+
+    /// the sha2-384 and sha2-512 multicodecs
+    const SHA2_384: u64 = 0x20;
+    const SHA2_512: u64 = 0x13;
+
+    fn check<D>(
+        key: &RsaPublicKey,
+        padding: RsaPadding,
+        hashed: &[u8],
+        signature: &[u8],
+    ) -> rsa::Result<()>
+    where
+        D: Digest + DynDigest + AssociatedOid + Send + Sync + 'static,
+    {
+        match padding {
+            RsaPadding::Pkcs1v15 => key.verify(Pkcs1v15Sign::new::<D>(), hashed, signature),
+            RsaPadding::Pss { salt_len } => {
+                key.verify(Pss::new_with_salt::<D>(salt_len), hashed, signature)
+            }
+        }
+    }
+
+    pub(super) fn verify(
+        hash: Codec,
+        padding: RsaPadding,
+        public_key: &[u8],
+        msg: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let hashed = match hash.code() {
+            SHA2_256 => Sha256::digest(msg).to_vec(),
+            SHA2_384 => Sha384::digest(msg).to_vec(),
+            SHA2_512 => Sha512::digest(msg).to_vec(),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("RSA with {:?}", hash))),
+        };
+        verify_prehash(hash, padding, public_key, &hashed, signature)
+    }
+
+    /// verify a signature over an already hashed payload
+    pub(super) fn verify_prehash(
+        hash: Codec,
+        padding: RsaPadding,
+        public_key: &[u8],
+        hashed: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let key = RsaPublicKey::from_pkcs1_der(public_key)
+            .map_err(|e| Error::InvalidPublicKey(e.to_string()))?;
+        let result = match hash.code() {
+            SHA2_256 => check::<Sha256>(&key, padding, hashed, signature),
+            SHA2_384 => check::<Sha384>(&key, padding, hashed, signature),
+            SHA2_512 => check::<Sha512>(&key, padding, hashed, signature),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("RSA with {:?}", hash))),
+        };
+        result.map_err(|_| Error::VerificationFailed)
+    }
+}
+
+#[cfg(feature = "openssl")]
+mod ossl {
+    use super::SHA2_256;
+    use crate::{Error, RsaPadding};
+    use multicodec::Codec;
+    use openssl::{
+        bn::{BigNum, BigNumContext},
+        ec::{EcGroup, EcKey, EcPoint},
+        ecdsa::EcdsaSig,
+        error::ErrorStack,
+        hash::MessageDigest,
+        md::Md,
+        nid::Nid,
+        pkey::PKey,
+        pkey_ctx::PkeyCtx,
+        rsa::{Padding, Rsa},
+        sign::RsaPssSaltlen,
+    };
+
+    /// the sha2-384 and sha2-512 multicodecs
+    const SHA2_384: u64 = 0x20;
+    const SHA2_512: u64 = 0x13;
+
+    /// the curves ECDSA signatures are verified over
+    #[derive(Clone, Copy)]
+    pub(super) enum Curve {
+        Secp256k1,
+        P256,
+    }
+
+    fn digest(hash: Codec, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        let md = match hash.code() {
+            SHA2_256 => MessageDigest::sha256(),
+            SHA2_384 => MessageDigest::sha384(),
+            SHA2_512 => MessageDigest::sha512(),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", hash))),
+        };
+        openssl::hash::hash(md, msg)
+            .map(|digest| digest.to_vec())
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    fn invalid_key(e: ErrorStack) -> Error {
+        Error::InvalidPublicKey(e.to_string())
+    }
+
+    fn verified(result: Result<bool, ErrorStack>) -> Result<(), Error> {
+        match result {
+            Ok(true) => Ok(()),
+            _ => Err(Error::VerificationFailed),
+        }
+    }
+
+    pub(super) fn verify_ecdsa(
+        curve: Curve,
+        hash: Codec,
+        public_key: &[u8],
+        msg: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(), Error> {
+        verify_ecdsa_prehash(curve, public_key, &digest(hash, msg)?, signature, der)
+    }
+
+    pub(super) fn verify_ecdsa_prehash(
+        curve: Curve,
+        public_key: &[u8],
+        digest: &[u8],
+        signature: &[u8],
+        der: bool,
+    ) -> Result<(), Error> {
+        let nid = match curve {
+            Curve::Secp256k1 => Nid::SECP256K1,
+            Curve::P256 => Nid::X9_62_PRIME256V1,
+        };
+        let group = EcGroup::from_curve_name(nid).map_err(invalid_key)?;
+        let mut ctx = BigNumContext::new().map_err(invalid_key)?;
+        let point = EcPoint::from_bytes(&group, public_key, &mut ctx).map_err(invalid_key)?;
+        let key = EcKey::from_public_key(&group, &point).map_err(invalid_key)?;
+        let sig = if der {
+            EcdsaSig::from_der(signature)
+        } else {
+            let rs = crate::vs::raw_ecdsa(signature).ok_or(Error::VerificationFailed)?;
+            BigNum::from_slice(&rs[..32])
+                .and_then(|r| EcdsaSig::from_private_components(r, BigNum::from_slice(&rs[32..])?))
+        }
+        .map_err(|_| Error::VerificationFailed)?;
+        verified(sig.verify(digest, &key))
+    }
+
+    pub(super) fn verify_rsa(
+        hash: Codec,
+        padding: RsaPadding,
+        public_key: &[u8],
+        msg: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        verify_rsa_prehash(hash, padding, public_key, &digest(hash, msg)?, signature)
+    }
+
+    pub(super) fn verify_rsa_prehash(
+        hash: Codec,
+        padding: RsaPadding,
+        public_key: &[u8],
+        digest: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let md = match hash.code() {
+            SHA2_256 => Md::sha256(),
+            SHA2_384 => Md::sha384(),
+            SHA2_512 => Md::sha512(),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", hash))),
+        };
+        // a salt length that doesn't fit an i32 would wrap into one of
+        // OpenSSL's negative special values and change the check
+        let salt_len = match padding {
+            RsaPadding::Pkcs1v15 => None,
+            RsaPadding::Pss { salt_len } => Some(i32::try_from(salt_len).map_err(|_| {
+                Error::InvalidAttributes(format!("RSA PSS salt length {}", salt_len))
+            })?),
+        };
+        let key = Rsa::public_key_from_der_pkcs1(public_key)
+            .and_then(PKey::from_rsa)
+            .map_err(invalid_key)?;
+        verified(PkeyCtx::new(&key).and_then(|mut ctx| {
+            ctx.verify_init()?;
+            ctx.set_signature_md(md)?;
+            match salt_len {
+                None => ctx.set_rsa_padding(Padding::PKCS1)?,
+                Some(salt_len) => {
+                    ctx.set_rsa_padding(Padding::PKCS1_PSS)?;
+                    ctx.set_rsa_mgf1_md(md)?;
+                    ctx.set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_len))?;
+                }
+            }
+            ctx.verify(digest, signature)
+        }))
+    }
+}
+
+#[cfg(feature = "aws-lc-rs")]
+mod aws_lc {
+    use crate::Error;
+    pub(super) use aws_lc_rs::signature::ED25519;
+    use aws_lc_rs::signature::{UnparsedPublicKey, VerificationAlgorithm};
+    #[cfg(not(feature = "openssl"))]
+    pub(super) use ecdsa::*;
+
+    /// aws-lc doesn't tell a malformed key from a bad signature so both
+    /// fail verification
+    pub(super) fn verify(
+        alg: &'static dyn VerificationAlgorithm,
+        public_key: &[u8],
+        msg: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        UnparsedPublicKey::new(alg, public_key)
+            .verify(msg, signature)
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    /// ECDSA goes through OpenSSL when both backends are enabled
+    #[cfg(not(feature = "openssl"))]
+    mod ecdsa {
+        use super::super::SHA2_256;
+        use crate::Error;
+        use aws_lc_rs::signature::{
+            VerificationAlgorithm, ECDSA_P256K1_SHA256_ASN1, ECDSA_P256K1_SHA256_FIXED,
+            ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA384_ASN1,
+            ECDSA_P256_SHA384_FIXED,
+        };
+        use multicodec::Codec;
+
+        /// the sha2-384 multicodec
+        const SHA2_384: u64 = 0x20;
+
+        /// the curves ECDSA signatures are verified over
+        #[derive(Clone, Copy)]
+        pub(in super::super) enum Curve {
+            Secp256k1,
+            P256,
+        }
+
+        /// whether aws-lc has an ES256 algorithm over this hash
+        pub(in super::super) fn is_supported(hash: Codec) -> bool {
+            matches!(hash.code(), SHA2_256 | SHA2_384)
+        }
+
+        pub(in super::super) fn verify_ecdsa(
+            curve: Curve,
+            hash: Codec,
+            public_key: &[u8],
+            msg: &[u8],
+            signature: &[u8],
+            der: bool,
+        ) -> Result<(), Error> {
+            let alg: &'static dyn VerificationAlgorithm = match (curve, hash.code(), der) {
+                (Curve::Secp256k1, SHA2_256, false) => &ECDSA_P256K1_SHA256_FIXED,
+                (Curve::Secp256k1, SHA2_256, true) => &ECDSA_P256K1_SHA256_ASN1,
+                (Curve::P256, SHA2_256, false) => &ECDSA_P256_SHA256_FIXED,
+                (Curve::P256, SHA2_256, true) => &ECDSA_P256_SHA256_ASN1,
+                (Curve::P256, SHA2_384, false) => &ECDSA_P256_SHA384_FIXED,
+                (Curve::P256, SHA2_384, true) => &ECDSA_P256_SHA384_ASN1,
+                _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", hash))),
+            };
+            let signature = if der {
+                signature
+            } else {
+                crate::vs::raw_ecdsa(signature).ok_or(Error::VerificationFailed)?
+            };
+            super::verify(alg, public_key, msg, signature)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Builder, Error};
+
+    #[cfg(feature = "ed25519-dalek")]
+    #[test]
+    fn test_verify_eddsa() {
+        use ed25519_dalek::{Signer, SigningKey};
+        use multicodec::Codec;
+
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let sig = sk.sign(b"for great justice");
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes(sig.to_bytes().as_slice())
+            .build();
+        let pk = sk.verifying_key().to_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+        assert!(matches!(
+            vs.verify(&pk, b"move every zig"),
+            Err(Error::VerificationFailed)
+        ));
+        assert!(matches!(
+            vs.verify(&pk[..31], b"for great justice"),
+            Err(Error::InvalidPublicKey(_))
+        ));
+    }
+
+    #[cfg(feature = "ed25519-dalek")]
+    #[test]
+    fn test_verify_dalek() {
+        use crate::Varsig;
+        use ed25519_dalek::{Signature, Signer, SigningKey};
+        use multicodec::Codec;
+
+        let sk = SigningKey::from_bytes(&[7u8; 32]);
+        let sig = sk.sign(b"for great justice");
+        let vs = Varsig::from(sig);
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+        assert!(vs
+            .verify_dalek(&sk.verifying_key(), b"for great justice")
+            .is_ok());
+        assert!(matches!(
+            vs.verify_dalek(&sk.verifying_key(), b"move every zig"),
+            Err(Error::VerificationFailed)
+        ));
+
+        let vs = Builder::newv2(Codec::Ed448Pub)
+            .with_signature_bytes([1u8; 114].as_slice())
+            .build();
+        assert!(matches!(
+            Signature::try_from(&vs),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+        assert!(matches!(
+            vs.verify_dalek(&sk.verifying_key(), b"for great justice"),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_verify_es256k() {
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+        use multicodec::Codec;
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let sig: Signature = sk.sign(b"for great justice");
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::try_from(0x12).unwrap())
+            .with_signature_bytes(&sig.to_bytes())
+            .build();
+        let pk = sk.verifying_key().to_sec1_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+        assert!(vs.verify(&pk, b"move every zig").is_err());
+
+        // a trailing byte is only allowed if it is a recovery id
+        for (v, ok) in [(&[27u8][..], true), (&[5], false), (&[0, 0], false)] {
+            let vs = Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes(&[&sig.to_bytes()[..], v].concat())
+                .build();
+            assert_eq!(vs.verify(&pk, b"for great justice").is_ok(), ok);
+        }
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_verify_multihash() {
+        use k256::ecdsa::SigningKey;
+        use multicodec::Codec;
+        use sha3::{Digest, Keccak256};
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pk = sk.verifying_key().to_sec1_bytes();
+        let digest = Keccak256::digest(b"for great justice");
+        let (sig, _) = sk.sign_prehash_recoverable(&digest).unwrap();
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes(&sig.to_bytes())
+            .build();
+        let mut mh = vec![0x1b, 0x20];
+        mh.extend_from_slice(&digest);
+        assert!(vs.verify_multihash(&pk, &mh).is_ok());
+
+        // a sha2-256 multihash doesn't match the keccak-256 hash attribute
+        mh[0] = 0x12;
+        assert!(matches!(
+            vs.verify_multihash(&pk, &mh),
+            Err(Error::InvalidPayload(_))
+        ));
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn test_verify_openssl() {
+        use crate::RsaPadding;
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+        use multicodec::Codec;
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let sig: Signature = sk.sign(b"for great justice");
+        let pk = sk.verifying_key().to_encoded_point(false);
+        for vs in [
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes(&sig.to_bytes())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_der()
+                .with_signature_bytes(sig.to_der().as_bytes())
+                .build(),
+        ] {
+            assert!(vs.verify(pk.as_bytes(), b"for great justice").is_ok());
+            assert!(matches!(
+                vs.verify(pk.as_bytes(), b"move every zig"),
+                Err(Error::VerificationFailed)
+            ));
+            assert!(matches!(
+                vs.verify(&pk.as_bytes()[..32], b"for great justice"),
+                Err(Error::InvalidPublicKey(_))
+            ));
+        }
+
+        // only a recovery id may follow a raw r || s
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(&[&sig.to_bytes()[..], &[5]].concat())
+            .build();
+        assert!(matches!(
+            vs.verify(pk.as_bytes(), b"for great justice"),
+            Err(Error::VerificationFailed)
+        ));
+
+        // a salt length OpenSSL can't take as is
+        let vs = Builder::newv2(Codec::RsaPub)
+            .with_rsa_params(Codec::Sha2256, 256)
+            .with_rsa_padding(RsaPadding::Pss {
+                salt_len: i32::MAX as usize + 1,
+            })
+            .with_signature_bytes([0u8; 256].as_slice())
+            .build();
+        assert!(matches!(
+            vs.verify(&[], b"for great justice"),
+            Err(Error::InvalidAttributes(_))
+        ));
+    }
+
+    #[cfg(feature = "aws-lc-rs")]
+    #[test]
+    fn test_verify_aws_lc() {
+        use ed25519_dalek::{Signer as _, SigningKey as EdSigningKey};
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+        use multicodec::Codec;
+
+        let sk = EdSigningKey::from_bytes(&[7u8; 32]);
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes(&sk.sign(b"for great justice").to_bytes())
+            .build();
+        let pk = sk.verifying_key().to_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+        assert!(matches!(
+            vs.verify(&pk, b"move every zig"),
+            Err(Error::VerificationFailed)
+        ));
+
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let sig: Signature = sk.sign(b"for great justice");
+        let pk = sk.verifying_key().to_encoded_point(false);
+        for vs in [
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes(&sig.to_bytes())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_der()
+                .with_signature_bytes(sig.to_der().as_bytes())
+                .build(),
+        ] {
+            assert!(vs.verify(pk.as_bytes(), b"for great justice").is_ok());
+            assert!(matches!(
+                vs.verify(pk.as_bytes(), b"move every zig"),
+                Err(Error::VerificationFailed)
+            ));
+        }
+
+        // only a recovery id may follow a raw r || s
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(&[&sig.to_bytes()[..], &[0, 0]].concat())
+            .build();
+        assert!(matches!(
+            vs.verify(pk.as_bytes(), b"for great justice"),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let vs = Builder::newv2_private(0x300000)
+            .with_signature_bytes([0u8; 32].as_slice())
+            .build();
+        assert!(matches!(
+            vs.verify(&[], b"for great justice"),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+    }
+}
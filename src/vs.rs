@@ -1,28 +1,241 @@
-use crate::Error;
+use crate::{
+    cow::{context_attributes, varuint_len},
+    registry, CowVarsig, DecodeLimits, Error, VarsigHeader,
+};
 use multibase::Base;
 use multicodec::Codec;
 use multitrait::TryDecodeFrom;
-use multiutil::{BaseEncoded, CodecInfo, EncodingInfo, Varbytes, Varuint};
-use ssh_key::{Algorithm, Signature};
-use std::fmt;
+use multiutil::{BaseEncoded, CodecInfo, EncodingInfo};
+use ssh_key::{Algorithm, EcdsaCurve, HashAlg, Signature};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+use subtle::{Choice, ConstantTimeEq};
 
 /// the varsig sigil
 pub const SIGIL: Codec = Codec::Varsig;
 
-/// a base encoded varsig
+/// a base encoded varsig. it is multiutil's [`BaseEncoded`], so this crate
+/// can't implement `FromStr` for it; parse one with
+/// `EncodedVarsig::try_from(s)`, or parse a [`Varsig`] with `s.parse()`,
+/// which also accepts hex.
 pub type EncodedVarsig = BaseEncoded<Varsig>;
 
+/// Query and override the base an [`EncodedVarsig`] is encoded in. One that
+/// was parsed or deserialized from a string keeps the base it arrived in and
+/// serializes in it again, rather than the preferred base for its codec.
+pub trait RetainedBase: Sized {
+    /// the base the varsig is encoded in
+    fn base(&self) -> Base;
+
+    /// switch to encoding the varsig in a different base
+    fn with_base(self, base: Base) -> Self;
+}
+
+impl RetainedBase for EncodedVarsig {
+    fn base(&self) -> Base {
+        // the multibase prefix is always the first character
+        self.to_string()
+            .chars()
+            .next()
+            .and_then(|c| Base::from_code(c).ok())
+            .unwrap_or_else(|| self.clone().to_inner().encoding())
+    }
+
+    fn with_base(self, base: Base) -> Self {
+        self.to_inner().into_encoded(base)
+    }
+}
+
+/// the most bytes a codec value can take up when varuint encoded
+pub const MAX_CODEC_LEN: usize = 10;
+
+/// the first attribute of a secp256k1 varsig that marks it as a BIP-340
+/// Schnorr signature rather than an ECDSA one. BIP-340 has no multicodec
+/// assigned, so this is a value from the private use range and other varsig
+/// implementations won't recognize it; it will move to the assigned code once
+/// there is one.
+pub const BIP340: u64 = 0x300340;
+
+/// the longest DER encoded ECDSA signature over a 256 bit curve
+pub(crate) const MAX_DER_LEN: usize = 72;
+
+/// the longest Ed25519ctx or Ed25519ph signing context RFC 8032 allows
+pub(crate) const MAX_ED25519_CONTEXT_LEN: usize = 255;
+
+/// the identity multicodec, the hash attribute of Ed25519ctx varsigs whose
+/// payload is not prehashed
+pub(crate) const IDENTITY: u64 = 0x00;
+
+/// the sha2-512 multicodec, the prehash of Ed25519ph varsigs
+pub(crate) const SHA2_512: u64 = 0x13;
+
+/// the flag in the attribute after the hash codec of a v2 ES256K or ES256
+/// varsig that records its signature as low-S normalized.
+///
+/// Experimental: the ECDSA flags attribute is specific to this crate and not
+/// part of the varsig spec, so other implementations will reject varsigs
+/// carrying it and its values may change. Nothing sets it unless asked to.
+pub const LOW_S: u64 = 0x01;
+
+/// the flag in the attribute after the hash codec of a v2 ES256K or ES256
+/// varsig that records its nonce as deterministic (RFC 6979) rather than
+/// random. experimental like the other ECDSA flags, see [`LOW_S`].
+pub const RFC6979: u64 = 0x02;
+
+/// the flag in the attribute after the hash codec of a v2 ES256K or ES256
+/// varsig that records its signature as ASN.1 DER rather than the raw
+/// r || s. experimental like the other ECDSA flags, see [`LOW_S`]; the
+/// conversions from k256 and p256 signatures always record the raw form.
+pub const DER: u64 = 0x04;
+
+/// all of the ES256K and ES256 flags
+pub(crate) const ECDSA_FLAGS: u64 = LOW_S | RFC6979 | DER;
+
+/// The padding scheme of an RSA signature
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RsaPadding {
+    /// PKCS #1 v1.5 padding, recorded by leaving out the padding attribute
+    #[default]
+    Pkcs1v15,
+    /// PSS padding, recorded as a third attribute holding the salt length
+    Pss {
+        /// the salt length in bytes
+        salt_len: usize,
+    },
+}
+
+/// The signature-specific attributes of a varsig, typed by signing algorithm
+/// so the hash and parameters can be read without knowing which attribute
+/// position holds them
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Attributes {
+    /// EdDSA and Ed448 varsigs, which have no attributes
+    None,
+    /// ES256K, ES256 and EIP-191 varsigs
+    Ecdsa {
+        /// the hash codec used to hash the payload
+        hash: Codec,
+        /// the signature is recorded as low-S normalized
+        low_s: bool,
+        /// the nonce is recorded as deterministic (RFC 6979)
+        rfc6979: bool,
+        /// the signature is recorded as ASN.1 DER rather than r || s
+        der: bool,
+    },
+    /// BIP-340 Schnorr varsigs
+    Schnorr {
+        /// the aux-data attribute, if the signer recorded one
+        aux: Option<u64>,
+    },
+    /// Ed25519ctx, Ed25519ph, sr25519 and EIP-712 varsigs, which bind the
+    /// signature to a context
+    Context {
+        /// the hash codec used to prehash the payload, if it is prehashed
+        hash: Option<Codec>,
+        /// the signing context, or the EIP-712 domain separator
+        context: Vec<u8>,
+    },
+    /// RSA varsigs
+    Rsa {
+        /// the hash codec used to hash the payload
+        hash: Codec,
+        /// the length of the key modulus in bytes
+        key_len: usize,
+        /// the padding scheme
+        padding: RsaPadding,
+    },
+    /// varsigs with an unknown or private use signing codec, whose
+    /// attributes are kept as given
+    Custom(Vec<u64>),
+}
+
+impl Attributes {
+    /// get the hash codec used to hash the payload, if the attributes record
+    /// one
+    pub fn hash(&self) -> Option<Codec> {
+        match self {
+            Attributes::Ecdsa { hash, .. } | Attributes::Rsa { hash, .. } => Some(*hash),
+            Attributes::Context { hash, .. } => *hash,
+            _ => None,
+        }
+    }
+}
+
+/// The components of a varsig header and its signature, for changing them
+/// without matching on every [`Varsig`] variant
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VarsigParts {
+    /// version of the varsig header
+    pub version: VarsigVersion,
+    /// signing codec value, including private use values
+    pub code: u64,
+    /// the payload encoding, None for v1 varsigs whose header has none
+    pub msg_encoding: Option<Codec>,
+    /// further payload encoding segments, v1 only
+    pub encoding_segments: Vec<Codec>,
+    /// signature-specific attributes
+    pub attributes: Vec<u64>,
+    /// the signature data
+    pub signature: Vec<u8>,
+}
+
+/// The varsig header versions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VarsigVersion {
+    /// the original varsig header with the attributes before the payload
+    /// encoding
+    V1,
+    /// the proposed header with the payload encoding first and counted
+    /// attributes and signature
+    #[default]
+    V2,
+}
+
+impl TryFrom<u8> for VarsigVersion {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            1 => Ok(VarsigVersion::V1),
+            2 => Ok(VarsigVersion::V2),
+            _ => Err(Error::InvalidVersion(v)),
+        }
+    }
+}
+
+impl From<VarsigVersion> for u8 {
+    fn from(v: VarsigVersion) -> Self {
+        match v {
+            VarsigVersion::V1 => 1,
+            VarsigVersion::V2 => 2,
+        }
+    }
+}
+
+impl fmt::Display for VarsigVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
 /// The main varsig structure
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub enum Varsig {
     /// Unknown signature
     Unknown {
         /// version of the varsig header
-        version: u8,
+        version: VarsigVersion,
         /// key codec value that is Unknown
         codec: Codec,
         /// msg encoding codec
         msg_encoding: Option<Codec>,
+        /// further payload encoding segments, v1 only
+        encoding_segments: Vec<Codec>,
         /// signature-specific attributes
         attributes: Vec<u64>,
         /// the signature-specific data
@@ -32,20 +245,463 @@ pub enum Varsig {
     /// EdDSA signature, key codec 0xED
     EdDSA {
         /// version of the varsig header
-        version: u8,
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// Ed25519ctx signature (RFC 8032) bound to a signing context, key codec
+    /// 0xED with the identity hash attribute. v2 only.
+    Ed25519ctx {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the signing context
+        context: Vec<u8>,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// Ed25519ph signature (RFC 8032) over the sha2-512 hash of the payload,
+    /// key codec 0xED with the sha2-512 hash attribute. v2 only.
+    Ed25519ph {
+        /// version of the varsig header
+        version: VarsigVersion,
         /// the payload encoding
         msg_encoding: Codec,
+        /// the signing context, which may be empty
+        context: Vec<u8>,
         /// the signature data
         signature: Vec<u8>,
     },
+
+    /// EdDSA signature over edwards448, key codec 0x1203
+    Ed448 {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// ES256K signature, key codec 0xE7
+    Es256K {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the hash codec used to hash the payload
+        hash: Codec,
+        /// the signature is recorded as low-S normalized, see
+        /// [`Varsig::normalize`]. v1 headers have no room for it.
+        low_s: bool,
+        /// the signature is recorded as using a deterministic RFC 6979
+        /// nonce. v1 headers have no room for it.
+        rfc6979: bool,
+        /// the signature is recorded as ASN.1 DER rather than the raw
+        /// r || s. v1 headers have no room for it.
+        der: bool,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// sr25519 (Schnorrkel) signature as used by Substrate, key codec 0xEF
+    Sr25519 {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the signing context, e.g. b"substrate". v1 headers have no room
+        /// for it so only v2 varsigs decode to this variant.
+        context: Vec<u8>,
+        /// the 64 byte signature data
+        signature: Vec<u8>,
+    },
+
+    /// Ethereum personal_sign signature (EIP-191): an EIP-191 payload hashed
+    /// with Keccak256 and signed with secp256k1, key codec 0xE7
+    Eip191 {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the encoding of the message the EIP-191 payload wraps, e.g.
+        /// DAG-CBOR. only v1 headers have room for it.
+        payload_encoding: Option<Codec>,
+        /// the signature data, r || s || v
+        signature: Vec<u8>,
+    },
+
+    /// Ethereum typed data signature (EIP-712), key codec 0xE7 with the
+    /// Keccak256 hash attribute followed by the domain separator. v2 only.
+    Eip712 {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding of the typed data
+        msg_encoding: Codec,
+        /// the hash of the typed data's EIP712Domain
+        domain_separator: [u8; 32],
+        /// the signature data, r || s || v
+        signature: Vec<u8>,
+    },
+
+    /// BIP-340 Schnorr signature over secp256k1, key codec 0xE7 with the
+    /// [`BIP340`] marker attribute
+    Schnorr {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the aux-data attribute, if the signer recorded one. v1 headers
+        /// have no room for it.
+        aux: Option<u64>,
+        /// the 64 byte signature data
+        signature: Vec<u8>,
+    },
+
+    /// ES256 signature over NIST P-256, key codec 0x1200
+    Es256 {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the hash codec used to hash the payload
+        hash: Codec,
+        /// the signature is recorded as low-S normalized, see
+        /// [`Varsig::normalize`]. v1 headers have no room for it.
+        low_s: bool,
+        /// the signature is recorded as using a deterministic RFC 6979
+        /// nonce. v1 headers have no room for it.
+        rfc6979: bool,
+        /// the signature is recorded as ASN.1 DER rather than the raw
+        /// r || s. v1 headers have no room for it.
+        der: bool,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// RSA signature (RS256, RS384, RS512, PS256, PS384, PS512), key codec
+    /// 0x1205
+    Rsa {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// the payload encoding
+        msg_encoding: Codec,
+        /// the hash codec used to hash the payload
+        hash: Codec,
+        /// the length of the key modulus, and so the signature, in bytes
+        key_len: usize,
+        /// the padding scheme. v1 headers have no room for the PSS salt length
+        /// so PSS signatures need v2.
+        padding: RsaPadding,
+        /// the signature data
+        signature: Vec<u8>,
+    },
+
+    /// Signature using a signing codec from the multicodec private use range.
+    /// [`Codec`] can't hold the code, so `codec()` returns `Codec::Identity`
+    /// for these; read it with [`Varsig::code`].
+    PrivateUse {
+        /// version of the varsig header
+        version: VarsigVersion,
+        /// private use signing codec value
+        code: u64,
+        /// msg encoding codec
+        msg_encoding: Option<Codec>,
+        /// further payload encoding segments, v1 only
+        encoding_segments: Vec<Codec>,
+        /// signature-specific attributes
+        attributes: Vec<u64>,
+        /// the signature-specific data
+        signature: Vec<u8>,
+    },
 }
 
 impl Varsig {
+    /// the largest encoding of an EdDSA varsig of either version and any
+    /// payload encoding, not counting the sigil: the version, the two byte
+    /// key codec, the payload encoding, an empty attribute count, the one
+    /// byte signature length and the 64 byte signature
+    pub const EDDSA_MAX_ENCODED_LEN: usize = 1 + 2 + MAX_CODEC_LEN + 1 + 1 + 64;
+
+    /// the largest encoding of an Ed448 varsig, laid out the same as
+    /// [`Varsig::EDDSA_MAX_ENCODED_LEN`] but with a 114 byte signature
+    pub const ED448_MAX_ENCODED_LEN: usize = 1 + 2 + MAX_CODEC_LEN + 1 + 1 + 114;
+
+    /// the largest encoding of an Ed25519ctx or Ed25519ph varsig: the
+    /// version, the two byte key codec, the payload encoding, the attribute
+    /// count, the one byte hash codec, the context length and up to 255
+    /// context bytes, each a varuint of up to two bytes, the one byte
+    /// signature length and the 64 byte signature
+    pub const ED25519CTX_MAX_ENCODED_LEN: usize = 1
+        + 2
+        + MAX_CODEC_LEN
+        + varuint_len(2 + MAX_ED25519_CONTEXT_LEN as u64)
+        + 1
+        + varuint_len(MAX_ED25519_CONTEXT_LEN as u64)
+        + 2 * MAX_ED25519_CONTEXT_LEN
+        + 1
+        + 64;
+
+    /// the largest encoding of an ES256K or EIP-191 varsig: the version, the
+    /// two byte key codec, the payload encoding, the attribute count, the hash
+    /// codec, the flags, the one byte signature length and a 72 byte DER
+    /// signature
+    pub const ES256K_MAX_ENCODED_LEN: usize =
+        1 + 2 + MAX_CODEC_LEN + 1 + MAX_CODEC_LEN + 1 + 1 + MAX_DER_LEN;
+
+    /// the largest encoding of an ES256 varsig, laid out the same as
+    /// [`Varsig::ES256K_MAX_ENCODED_LEN`]
+    pub const ES256_MAX_ENCODED_LEN: usize =
+        1 + 2 + MAX_CODEC_LEN + 1 + MAX_CODEC_LEN + 1 + 1 + MAX_DER_LEN;
+
+    /// the largest encoding of an EIP-712 varsig: the version, the two byte
+    /// key codec, the payload encoding, the attribute count, the keccak-256
+    /// codec, the domain separator length and its 32 bytes, each a varuint of
+    /// up to two bytes, the one byte signature length and the 65 byte
+    /// signature
+    pub const EIP712_MAX_ENCODED_LEN: usize = 1 + 2 + MAX_CODEC_LEN + 1 + 1 + 1 + 2 * 32 + 1 + 65;
+
+    /// the largest encoding of a BIP-340 Schnorr varsig: the version, the two
+    /// byte key codec, the payload encoding, the attribute count, the BIP-340
+    /// marker, the aux data attribute, the one byte signature length and the
+    /// 64 byte signature
+    pub const SCHNORR_MAX_ENCODED_LEN: usize =
+        1 + 2 + MAX_CODEC_LEN + 1 + varuint_len(BIP340) + MAX_CODEC_LEN + 1 + 64;
+
+    /// the largest encoding of an RSA varsig with a key_len byte modulus: the
+    /// version, the two byte key codec, the payload encoding, the attribute
+    /// count, the hash codec, the key length, the PSS salt length, the
+    /// signature length and the key_len byte signature
+    pub const fn rsa_max_encoded_len(key_len: usize) -> usize {
+        1 + 2
+            + MAX_CODEC_LEN
+            + 1
+            + MAX_CODEC_LEN
+            + varuint_len(key_len as u64)
+            + MAX_CODEC_LEN
+            + varuint_len(key_len as u64)
+            + key_len
+    }
+
+    /// the largest encoding of an sr25519 varsig with a context_len byte
+    /// signing context: the version, the two byte key codec, the payload
+    /// encoding, the attribute count, the context length and its bytes, each
+    /// a varuint of up to two bytes, the one byte signature length and the 64
+    /// byte signature
+    pub const fn sr25519_max_encoded_len(context_len: usize) -> usize {
+        1 + 2
+            + MAX_CODEC_LEN
+            + varuint_len(1 + context_len as u64)
+            + varuint_len(context_len as u64)
+            + 2 * context_len
+            + 1
+            + 64
+    }
+
+    /// the largest encoding of a varsig using the given signing codec, if its
+    /// size is bounded, covering every variant that shares the codec. RSA and
+    /// sr25519 varsigs are bounded by their key length and signing context;
+    /// see [`Varsig::rsa_max_encoded_len`] and
+    /// [`Varsig::sr25519_max_encoded_len`]. v1 headers with more than one
+    /// payload encoding segment aren't bounded.
+    pub fn max_encoded_len(codec: Codec) -> Option<usize> {
+        match codec {
+            Codec::Ed25519Pub => Some(Self::ED25519CTX_MAX_ENCODED_LEN),
+            Codec::Ed448Pub => Some(Self::ED448_MAX_ENCODED_LEN),
+            Codec::Secp256K1Pub => Some(
+                Self::ES256K_MAX_ENCODED_LEN
+                    .max(Self::EIP712_MAX_ENCODED_LEN)
+                    .max(Self::SCHNORR_MAX_ENCODED_LEN),
+            ),
+            Codec::P256Pub => Some(Self::ES256_MAX_ENCODED_LEN),
+            _ => None,
+        }
+    }
+
+    /// the number of bytes this varsig encodes to, not counting the sigil
+    pub fn encoded_len(&self) -> usize {
+        CowVarsig::from(self).encoded_len()
+    }
+
+    /// encode into the start of a preallocated buffer, returning the number
+    /// of bytes written. the buffer must be at least
+    /// [`Varsig::encoded_len`] long.
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        CowVarsig::from(self).encode_into(buf)
+    }
+
+    /// encode straight into a file, socket or other writer, returning the
+    /// number of bytes written
+    pub fn write_to<W: std::io::Write>(&self, w: W) -> std::io::Result<usize> {
+        CowVarsig::from(self).write_to(w)
+    }
+
+    /// decode a varsig, rejecting it if it declares more attributes or a
+    /// longer signature than the limits allow. `try_decode_from` uses
+    /// [`DecodeLimits::default`].
+    pub fn try_decode_with_limits(
+        bytes: &[u8],
+        limits: DecodeLimits,
+    ) -> Result<(Self, &[u8]), Error> {
+        let (cv, ptr) = CowVarsig::try_decode_with_limits(bytes, limits)?;
+        Ok((cv.into_owned(), ptr))
+    }
+
+    /// decode a varsig for a validator that must not accept sloppy
+    /// encodings: the signing codec must be built in or registered, the
+    /// header must pass [`Varsig::validate`], every varuint must be minimally
+    /// encoded and nothing may follow the varsig. `try_from` and
+    /// `try_decode_from` remain lenient.
+    pub fn from_bytes_strict(bytes: &[u8]) -> Result<Self, Error> {
+        let (cv, ptr) = CowVarsig::try_decode_from(bytes)?;
+        if !ptr.is_empty() {
+            return Err(Error::TrailingBytes(ptr.len()));
+        }
+        if registry::layout(cv.code).is_none() && cv.codec() != Some(Codec::Sr25519Pub) {
+            return Err(Error::UnknownCodec(cv.code));
+        }
+        cv.validate()?;
+        let vs = cv.into_owned();
+        vs.check_canonical(bytes)?;
+        Ok(vs)
+    }
+
+    /// decode a varsig for content-addressed storage, where the bytes are
+    /// the identity: nothing may follow the varsig and encoding it again
+    /// must reproduce the input exactly, so `encode(decode(b)) == b`. unlike
+    /// [`Varsig::from_bytes_strict`] any codec and header the decoder accepts
+    /// is allowed.
+    pub fn from_bytes_canonical(bytes: &[u8]) -> Result<Self, Error> {
+        let (vs, ptr) = Self::try_decode_from(bytes)?;
+        if !ptr.is_empty() {
+            return Err(Error::TrailingBytes(ptr.len()));
+        }
+        vs.check_canonical(bytes)?;
+        Ok(vs)
+    }
+
+    /// check that re-encoding reproduces the input exactly, with or without
+    /// the sigil
+    fn check_canonical(&self, bytes: &[u8]) -> Result<(), Error> {
+        let untagged = match Codec::try_decode_from(bytes) {
+            Ok((sigil, ptr)) if sigil == SIGIL => ptr,
+            _ => bytes,
+        };
+        let cv = CowVarsig::from(self);
+        if cv.encoded_len() != untagged.len() {
+            return Err(Error::NonCanonical);
+        }
+        let mut v = vec![0; untagged.len()];
+        cv.encode_into(&mut v)?;
+        if v != untagged {
+            return Err(Error::NonCanonical);
+        }
+        Ok(())
+    }
+
+    /// get the header without the signature, to send or store separately
+    pub fn header(&self) -> VarsigHeader {
+        VarsigHeader::from(self)
+    }
+
+    /// split into the header components and the signature
+    pub fn into_parts(self) -> VarsigParts {
+        let cv = CowVarsig::from(self);
+        VarsigParts {
+            version: cv.version,
+            code: cv.code,
+            msg_encoding: cv.msg_encoding,
+            encoding_segments: cv.encoding_segments,
+            attributes: cv.attributes,
+            signature: cv.signature.into_owned(),
+        }
+    }
+
+    /// put a varsig back together from its components, checking them against
+    /// the rules for the signing algorithm
+    pub fn from_parts(parts: VarsigParts) -> Result<Self, Error> {
+        let cv = CowVarsig {
+            version: parts.version,
+            code: parts.code,
+            msg_encoding: parts.msg_encoding,
+            encoding_segments: parts.encoding_segments,
+            attributes: parts.attributes,
+            signature: Cow::Owned(parts.signature),
+        };
+        cv.validate()?;
+        Ok(cv.into_owned())
+    }
+
+    /// re-emit a v1 varsig with a v2 header. fails if the v1 header has
+    /// payload encoding segments v2 has no room for.
+    pub fn upgrade_to_v2(&self) -> Result<Self, Error> {
+        let mut cv = CowVarsig::from(self);
+        if !cv.encoding_segments.is_empty() {
+            return Err(Error::InvalidEncodingInfo(
+                "v2 headers have a single payload encoding".to_string(),
+            ));
+        }
+        cv.version = VarsigVersion::V2;
+        Ok(cv.into_owned())
+    }
+
+    /// re-emit a v2 varsig with a v1 header. v1 headers don't record how
+    /// many attributes there are so this fails unless the signing codec's
+    /// layout has exactly as many as the varsig, which rules out v2-only
+    /// data such as signing contexts, aux data and ECDSA flags.
+    pub fn downgrade_to_v1(&self) -> Result<Self, Error> {
+        let mut cv = CowVarsig::from(self);
+        let expected = registry::layout(cv.code).map_or(0, |l| l.attributes);
+        if cv.attributes.len() != expected {
+            return Err(Error::InvalidAttributes(format!(
+                "v1 headers have room for {} attributes, got {}",
+                expected,
+                cv.attributes.len()
+            )));
+        }
+        cv.version = VarsigVersion::V1;
+        Ok(cv.into_owned())
+    }
+
     /// get the version
-    pub fn version(&self) -> u8 {
+    pub fn version(&self) -> VarsigVersion {
         match self {
             Varsig::Unknown { version, .. } => *version,
             Varsig::EdDSA { version, .. } => *version,
+            Varsig::Ed25519ctx { version, .. } => *version,
+            Varsig::Ed25519ph { version, .. } => *version,
+            Varsig::Ed448 { version, .. } => *version,
+            Varsig::Es256K { version, .. } => *version,
+            Varsig::Es256 { version, .. } => *version,
+            Varsig::Eip191 { version, .. } => *version,
+            Varsig::Eip712 { version, .. } => *version,
+            Varsig::Schnorr { version, .. } => *version,
+            Varsig::Sr25519 { version, .. } => *version,
+            Varsig::Rsa { version, .. } => *version,
+            Varsig::PrivateUse { version, .. } => *version,
+        }
+    }
+
+    /// get the signing codec value, including private use values that have no
+    /// [`Codec`] representation
+    pub fn code(&self) -> u64 {
+        match self {
+            Varsig::PrivateUse { code, .. } => *code,
+            _ => self.codec().code(),
+        }
+    }
+
+    /// get the payload encoding as the header records it. v1 varsigs decoded
+    /// without a known layout have none.
+    pub(crate) fn header_msg_encoding(&self) -> Option<Codec> {
+        match self {
+            Varsig::Unknown { msg_encoding, .. } | Varsig::PrivateUse { msg_encoding, .. } => {
+                *msg_encoding
+            }
+            _ => Some(self.msg_encoding()),
         }
     }
 
@@ -54,24 +710,314 @@ impl Varsig {
         match self {
             Varsig::Unknown { msg_encoding, .. } => msg_encoding.unwrap_or(Codec::Raw),
             Varsig::EdDSA { msg_encoding, .. } => *msg_encoding,
+            Varsig::Ed25519ctx { msg_encoding, .. } => *msg_encoding,
+            Varsig::Ed25519ph { msg_encoding, .. } => *msg_encoding,
+            Varsig::Ed448 { msg_encoding, .. } => *msg_encoding,
+            Varsig::Es256K { msg_encoding, .. } => *msg_encoding,
+            Varsig::Es256 { msg_encoding, .. } => *msg_encoding,
+            Varsig::Eip191 { .. } => Codec::Eip191,
+            Varsig::Eip712 { msg_encoding, .. } => *msg_encoding,
+            Varsig::Schnorr { msg_encoding, .. } => *msg_encoding,
+            Varsig::Sr25519 { msg_encoding, .. } => *msg_encoding,
+            Varsig::Rsa { msg_encoding, .. } => *msg_encoding,
+            Varsig::PrivateUse { msg_encoding, .. } => msg_encoding.unwrap_or(Codec::Raw),
+        }
+    }
+
+    /// get the payload encoding segments that follow the payload encoding in
+    /// v1 headers
+    pub fn encoding_segments(&self) -> Vec<Codec> {
+        match self {
+            Varsig::Unknown {
+                encoding_segments, ..
+            }
+            | Varsig::PrivateUse {
+                encoding_segments, ..
+            } => encoding_segments.clone(),
+            Varsig::Eip191 {
+                payload_encoding, ..
+            } => payload_encoding.iter().copied().collect(),
+            _ => Vec::default(),
         }
     }
 
     /// get the attributes
     pub fn attributes(&self) -> Vec<u64> {
+        self.attributes_slice().into_owned()
+    }
+
+    /// get the attributes without copying them. only varsigs that store their
+    /// attributes as given, [`Varsig::Unknown`] and [`Varsig::PrivateUse`],
+    /// can lend them; the rest are built from the variant's fields.
+    pub fn attributes_slice(&self) -> Cow<'_, [u64]> {
+        let attributes = match self {
+            Varsig::Unknown { attributes, .. } | Varsig::PrivateUse { attributes, .. } => {
+                return Cow::Borrowed(attributes)
+            }
+            Varsig::EdDSA { .. } | Varsig::Ed448 { .. } => return Cow::Borrowed(&[]),
+            Varsig::Es256K {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            }
+            | Varsig::Es256 {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            } => {
+                let flags = (u64::from(*low_s) * LOW_S)
+                    | (u64::from(*rfc6979) * RFC6979)
+                    | (u64::from(*der) * DER);
+                [hash.code()]
+                    .into_iter()
+                    .chain(Some(flags).filter(|f| *f != 0))
+                    .collect()
+            }
+            Varsig::Eip191 { .. } => vec![Codec::Keccak256.code()],
+            Varsig::Eip712 {
+                domain_separator, ..
+            } => [Codec::Keccak256.code()]
+                .into_iter()
+                .chain(context_attributes(domain_separator))
+                .collect(),
+            Varsig::Schnorr { aux, .. } => [BIP340].into_iter().chain(*aux).collect(),
+            Varsig::Ed25519ctx { context, .. } => [IDENTITY]
+                .into_iter()
+                .chain(context_attributes(context))
+                .collect(),
+            Varsig::Ed25519ph { context, .. } => [SHA2_512]
+                .into_iter()
+                .chain(context_attributes(context))
+                .collect(),
+            Varsig::Sr25519 { context, .. } => context_attributes(context).collect(),
+            Varsig::Rsa {
+                hash,
+                key_len,
+                padding,
+                ..
+            } => {
+                let mut attributes = vec![hash.code(), *key_len as u64];
+                if let RsaPadding::Pss { salt_len } = padding {
+                    attributes.push(*salt_len as u64);
+                }
+                attributes
+            }
+        };
+        Cow::Owned(attributes)
+    }
+
+    /// get the attributes typed by signing algorithm
+    pub fn typed_attributes(&self) -> Attributes {
+        match self {
+            Varsig::Unknown { attributes, .. } | Varsig::PrivateUse { attributes, .. } => {
+                Attributes::Custom(attributes.clone())
+            }
+            Varsig::EdDSA { .. } | Varsig::Ed448 { .. } => Attributes::None,
+            Varsig::Es256K {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            }
+            | Varsig::Es256 {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            } => Attributes::Ecdsa {
+                hash: *hash,
+                low_s: *low_s,
+                rfc6979: *rfc6979,
+                der: *der,
+            },
+            Varsig::Eip191 { .. } => Attributes::Ecdsa {
+                hash: Codec::Keccak256,
+                low_s: false,
+                rfc6979: false,
+                der: false,
+            },
+            Varsig::Eip712 {
+                domain_separator, ..
+            } => Attributes::Context {
+                hash: Some(Codec::Keccak256),
+                context: domain_separator.to_vec(),
+            },
+            Varsig::Schnorr { aux, .. } => Attributes::Schnorr { aux: *aux },
+            Varsig::Ed25519ctx { context, .. } => Attributes::Context {
+                hash: None,
+                context: context.clone(),
+            },
+            Varsig::Ed25519ph { context, .. } => Attributes::Context {
+                hash: Some(Codec::Sha2512),
+                context: context.clone(),
+            },
+            Varsig::Sr25519 { context, .. } => Attributes::Context {
+                hash: None,
+                context: context.clone(),
+            },
+            Varsig::Rsa {
+                hash,
+                key_len,
+                padding,
+                ..
+            } => Attributes::Rsa {
+                hash: *hash,
+                key_len: *key_len,
+                padding: *padding,
+            },
+        }
+    }
+
+    /// get the hash codec used to hash the payload before signing, if the
+    /// header records one. varsigs with unknown or private use signing codecs
+    /// have no known hash attribute.
+    pub fn hash_codec(&self) -> Option<Codec> {
+        self.typed_attributes().hash()
+    }
+
+    /// get the recovery id of a secp256k1 ECDSA varsig whose signature is
+    /// r || s || v, normalized to 0 or 1 from either the raw or the Ethereum
+    /// (27 or 28) form of v. v stays in the signature bytes so that encodings
+    /// round trip unchanged.
+    pub fn recovery_id(&self) -> Option<u8> {
+        match self {
+            Varsig::Es256K {
+                signature,
+                der: false,
+                ..
+            }
+            | Varsig::Eip191 { signature, .. }
+            | Varsig::Eip712 { signature, .. }
+                if signature.len() == 65 =>
+            {
+                recovery_byte(signature[64])
+            }
+            _ => None,
+        }
+    }
+
+    /// get the padding scheme of an RSA varsig
+    pub fn padding(&self) -> Option<RsaPadding> {
         match self {
-            Varsig::Unknown { attributes, .. } => attributes.clone(),
-            Varsig::EdDSA { .. } => Vec::default(),
+            Varsig::Rsa { padding, .. } => Some(*padding),
+            _ => None,
         }
     }
 
+    /// whether the signature of an ES256K or ES256 varsig is recorded as
+    /// ASN.1 DER rather than the raw r || s
+    pub fn is_der(&self) -> bool {
+        matches!(
+            self,
+            Varsig::Es256K { der: true, .. } | Varsig::Es256 { der: true, .. }
+        )
+    }
+
     /// get the signature vector
     pub fn signature(&self) -> Vec<u8> {
+        self.signature_bytes().to_vec()
+    }
+
+    /// get the signature data without copying it
+    pub fn signature_bytes(&self) -> &[u8] {
         match self {
-            Varsig::Unknown { signature, .. } => signature.clone(),
-            Varsig::EdDSA { signature, .. } => signature.clone(),
+            Varsig::Unknown { signature, .. } => signature,
+            Varsig::EdDSA { signature, .. } => signature,
+            Varsig::Ed25519ctx { signature, .. } => signature,
+            Varsig::Ed25519ph { signature, .. } => signature,
+            Varsig::Ed448 { signature, .. } => signature,
+            Varsig::Es256K { signature, .. } => signature,
+            Varsig::Es256 { signature, .. } => signature,
+            Varsig::Eip191 { signature, .. } => signature,
+            Varsig::Eip712 { signature, .. } => signature,
+            Varsig::Schnorr { signature, .. } => signature,
+            Varsig::Sr25519 { signature, .. } => signature,
+            Varsig::Rsa { signature, .. } => signature,
+            Varsig::PrivateUse { signature, .. } => signature,
+        }
+    }
+
+    /// check the header against the rules for its signing algorithm
+    pub fn validate(&self) -> Result<(), Error> {
+        CowVarsig::from(self).validate()
+    }
+
+    /// change the payload encoding, keeping the varsig unchanged if the
+    /// result is not valid for the signing algorithm
+    pub fn set_msg_encoding(&mut self, codec: Codec) -> Result<(), Error> {
+        self.modify(|cv| cv.msg_encoding = Some(codec))
+    }
+
+    /// replace the signature-specific attributes, keeping the varsig unchanged
+    /// if the result is not valid for the signing algorithm
+    pub fn set_attributes(&mut self, attributes: Vec<u64>) -> Result<(), Error> {
+        self.modify(|cv| cv.attributes = attributes)
+    }
+
+    /// replace the attribute at index, or append it if index is the number of
+    /// attributes, and revalidate
+    pub fn with_attribute(mut self, index: usize, value: u64) -> Result<Self, Error> {
+        let mut attributes = self.attributes();
+        match index.cmp(&attributes.len()) {
+            Ordering::Less => attributes[index] = value,
+            Ordering::Equal => attributes.push(value),
+            Ordering::Greater => {
+                return Err(Error::InvalidAttributes(format!(
+                    "attribute index {} out of range",
+                    index
+                )))
+            }
+        }
+        self.set_attributes(attributes)?;
+        Ok(self)
+    }
+
+    /// change the payload encoding and revalidate
+    pub fn with_msg_encoding(mut self, codec: Codec) -> Result<Self, Error> {
+        self.set_msg_encoding(codec)?;
+        Ok(self)
+    }
+
+    fn modify(&mut self, f: impl FnOnce(&mut CowVarsig)) -> Result<(), Error> {
+        let mut cv = CowVarsig::from(self.clone());
+        f(&mut cv);
+        cv.validate()?;
+        *self = cv.into_owned();
+        Ok(())
+    }
+
+    /// encode this varsig prefixed with the varsig sigil so that it is self
+    /// identifying when mixed in with other multiformats. both versions use
+    /// the same sigil; the version after it tells them apart.
+    pub fn to_tagged_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = SIGIL.into();
+        v.append(&mut self.clone().into());
+        v
+    }
+
+    /// decode a varsig that must be prefixed with the varsig sigil
+    pub fn from_tagged_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match Codec::try_decode_from(bytes) {
+            Ok((sigil, ptr)) if sigil == SIGIL => Self::try_from(ptr),
+            _ => Err(Error::MissingSigil),
         }
     }
+
+    /// wrap a copy of this varsig in an [`EncodedVarsig`] using the given base
+    pub fn to_encoded(&self, base: Base) -> EncodedVarsig {
+        BaseEncoded::new_base(base, self.clone())
+    }
+
+    /// wrap this varsig in an [`EncodedVarsig`] using the given base
+    pub fn into_encoded(self, base: Base) -> EncodedVarsig {
+        BaseEncoded::new_base(base, self)
+    }
 }
 
 impl CodecInfo for Varsig {
@@ -80,56 +1026,44 @@ impl CodecInfo for Varsig {
         SIGIL
     }
 
-    /// Return the signing codec for the varsig
+    /// Return the signing codec for the varsig. [`Codec`] only has variants
+    /// for the codes in the multicodec table, which never assigns the private
+    /// use range, so there is no value to return for a private use varsig.
+    /// `Codec::Identity` is returned instead, since it is never a signing
+    /// codec and can't be mistaken for one; use [`Varsig::code`] to get the
+    /// private use value.
     fn codec(&self) -> Codec {
         match self {
             Varsig::Unknown { codec, .. } => *codec,
             Varsig::EdDSA { .. } => Codec::Ed25519Pub,
+            Varsig::Ed25519ctx { .. } => Codec::Ed25519Pub,
+            Varsig::Ed25519ph { .. } => Codec::Ed25519Pub,
+            Varsig::Ed448 { .. } => Codec::Ed448Pub,
+            Varsig::Es256K { .. } => Codec::Secp256K1Pub,
+            Varsig::Es256 { .. } => Codec::P256Pub,
+            Varsig::Eip191 { .. } => Codec::Secp256K1Pub,
+            Varsig::Eip712 { .. } => Codec::Secp256K1Pub,
+            Varsig::Schnorr { .. } => Codec::Secp256K1Pub,
+            Varsig::Sr25519 { .. } => Codec::Sr25519Pub,
+            Varsig::Rsa { .. } => Codec::RsaPub,
+            Varsig::PrivateUse { .. } => Codec::Identity,
         }
     }
 }
 
 impl EncodingInfo for Varsig {
     fn preferred_encoding() -> Base {
-        Base::Base16Lower
+        registry::default_encoding()
     }
 
     fn encoding(&self) -> Base {
-        Self::preferred_encoding()
+        registry::preferred_encoding(self.code())
     }
 }
 
 impl Into<Vec<u8>> for Varsig {
     fn into(self) -> Vec<u8> {
-        let mut v = Vec::default();
-        // add in the version
-        v.append(&mut Varuint(self.version()).into());
-        // add in the signing codec
-        v.append(&mut self.codec().into());
-        let attributes = self.attributes();
-        let mut signature = self.signature();
-        if self.version() == 2 {
-            // add in the payload encoding
-            v.append(&mut self.msg_encoding().into());
-            // add in the number signature specific attributes
-            v.append(&mut Varuint(attributes.len()).into());
-            // add in the signature specific attributes
-            attributes
-                .iter()
-                .for_each(|a| v.append(&mut Varuint(*a).into()));
-            // add in the signature data
-            v.append(&mut Varbytes(signature).into());
-        } else {
-            // add in the signature specific attributes
-            for ss in attributes {
-                v.append(&mut Varuint(ss).into());
-            }
-            // add in the payload encoding
-            v.append(&mut self.msg_encoding().into());
-            // add the signature data
-            v.append(&mut signature);
-        }
-        v
+        CowVarsig::from(&self).into()
     }
 }
 
@@ -142,128 +1076,677 @@ impl<'a> TryFrom<&'a [u8]> for Varsig {
     }
 }
 
-impl<'a> TryDecodeFrom<'a> for Varsig {
-    type Error = Error;
+/// normalize a recovery id in either the raw (0 or 1) or the Ethereum (27 or
+/// 28) form to 0 or 1
+fn recovery_byte(v: u8) -> Option<u8> {
+    match v {
+        0 | 1 => Some(v),
+        27 | 28 => Some(v - 27),
+        _ => None,
+    }
+}
 
-    fn try_decode_from(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
-        // decode the version
-        let (version, ptr) = Varuint::<u8>::try_decode_from(bytes)?;
-        let version = version.to_inner();
-        // decoded the signing coded
-        let (codec, ptr) = Codec::try_decode_from(ptr)?;
-        // get the payload encoding if v2
-        let (msg_encoding, ptr) = match version {
-            1 => (None, ptr),
-            2 => {
-                // parse the encoding codec for the data that was signed
-                let (msg_encoding, ptr) = Codec::try_decode_from(ptr)?;
-                (Some(msg_encoding), ptr)
-            }
-            _ => return Err(Error::InvalidVersion(version)),
-        };
-        // get the attributes if v2
-        let (attributes, ptr) = match version {
-            1 => (Vec::default(), ptr),
-            2 => {
-                // parse the number of attributes
-                let (len, ptr) = Varuint::<usize>::try_decode_from(ptr)?;
-                let len = len.to_inner();
-
-                let mut v = Vec::with_capacity(len);
-                let mut p = ptr;
-                for _ in 0..len {
-                    // parse the varuint attribute
-                    let (attribute, ptr) = Varuint::<u64>::try_decode_from(p)?;
-                    v.push(attribute.to_inner());
-                    p = ptr;
-                }
-                (v, p)
+/// the r || s of a raw ECDSA signature. it must be exactly 64 bytes, or 65
+/// when the last byte is a recovery id; anything else would let trailing
+/// bytes ride along with a valid signature.
+#[cfg(any(feature = "k256", feature = "openssl", feature = "aws-lc-rs"))]
+pub(crate) fn raw_ecdsa(signature: &[u8]) -> Option<&[u8]> {
+    match signature.len() {
+        64 => Some(signature),
+        65 if recovery_byte(signature[64]).is_some() => Some(&signature[..64]),
+        _ => None,
+    }
+}
+
+/// decode the bytes of a varsig string: a multibase string, or hex with or
+/// without a 0x prefix. base16 multibase strings have an odd length, the
+/// prefix and two digits per byte, so bare hex is never mistaken for one.
+pub(crate) fn decode_str(s: &str) -> Result<Vec<u8>, multibase::Error> {
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    let is_hex = !hex.is_empty()
+        && hex.len().is_multiple_of(2)
+        && hex.bytes().all(|b| b.is_ascii_hexdigit());
+    if is_hex {
+        Base::Base16Lower.decode(hex.to_ascii_lowercase())
+    } else {
+        multibase::decode(s).map(|(_, bytes)| bytes)
+    }
+}
+
+/// Parse a varsig from a multibase string or hex with or without a 0x
+/// prefix, e.g. in command line arguments and config files. Nothing may
+/// follow the varsig.
+impl FromStr for Varsig {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_str(s)?;
+        let (vs, ptr) = Self::try_decode_from(bytes.as_slice())?;
+        if !ptr.is_empty() {
+            return Err(Error::TrailingBytes(ptr.len()));
+        }
+        Ok(vs)
+    }
+}
+
+/// Convert back into an SSH signature for SSH tooling. Ed25519, ES256 with
+/// SHA-256, in either r || s or DER form, and PKCS #1 v1.5 RSA with SHA-256
+/// or SHA-512 varsigs are supported. SSH has no secp256k1 signatures so
+/// ES256K varsigs are not.
+impl TryFrom<&Varsig> for Signature {
+    type Error = Error;
+
+    fn try_from(vs: &Varsig) -> Result<Self, Self::Error> {
+        let (algorithm, data) = match vs {
+            Varsig::EdDSA { signature, .. } => (Algorithm::Ed25519, signature.clone()),
+            Varsig::Es256 {
+                hash: Codec::Sha2256,
+                der,
+                signature,
+                ..
+            } => (
+                Algorithm::Ecdsa {
+                    curve: EcdsaCurve::NistP256,
+                },
+                if *der {
+                    der_to_ssh_ecdsa(signature)?
+                } else {
+                    raw_to_ssh_ecdsa(signature)
+                },
+            ),
+            Varsig::Rsa {
+                hash,
+                padding: RsaPadding::Pkcs1v15,
+                signature,
+                ..
+            } if matches!(hash, Codec::Sha2256 | Codec::Sha2512) => {
+                let hash = if *hash == Codec::Sha2256 {
+                    HashAlg::Sha256
+                } else {
+                    HashAlg::Sha512
+                };
+                (Algorithm::Rsa { hash: Some(hash) }, signature.clone())
             }
-            _ => return Err(Error::InvalidVersion(version)),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs.codec()))),
         };
-        let (signature, ptr) = match version {
-            1 => match codec {
-                Codec::Ed25519Pub => {
-                    let s = ptr[..64].to_vec();
-                    let p = &ptr[64..];
-                    (s, p)
-                }
-                _ => {
-                    let s = ptr[..].to_vec();
-                    let p = &ptr[..];
-                    (s, p)
-                }
-            },
-            2 => {
-                // parse the signature byts array
-                let (s, p) = Varbytes::try_decode_from(ptr)?;
-                (s.to_inner(), p)
+        Ok(Signature::new(algorithm, data)?)
+    }
+}
+
+impl TryFrom<Varsig> for Signature {
+    type Error = Error;
+
+    fn try_from(vs: Varsig) -> Result<Self, Self::Error> {
+        Signature::try_from(&vs)
+    }
+}
+
+/// SSH ECDSA signatures are the mpints r and s; varsigs carry the fixed
+/// length r || s
+fn ssh_ecdsa_to_raw(blob: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut raw = vec![0u8; 64];
+    let mut ptr = blob;
+    for half in raw.chunks_mut(32) {
+        let len = match ptr {
+            [a, b, c, d, ..] => u32::from_be_bytes([*a, *b, *c, *d]) as usize,
+            _ => {
+                return Err(Error::InvalidSignatureEncoding(
+                    "truncated SSH ECDSA signature".to_string(),
+                ))
             }
-            _ => return Err(Error::InvalidVersion(version)),
         };
+        if ptr.len() < 4 + len {
+            return Err(Error::InvalidSignatureEncoding(
+                "truncated SSH ECDSA signature".to_string(),
+            ));
+        }
+        let (mpint, rest) = ptr[4..].split_at(len);
+        // mpints carry a leading zero when the high bit is set
+        let n = match mpint.iter().position(|b| *b != 0) {
+            Some(i) => &mpint[i..],
+            None => &[],
+        };
+        if n.len() > 32 {
+            return Err(Error::InvalidSignatureEncoding(
+                "SSH ECDSA signature component is too long".to_string(),
+            ));
+        }
+        half[32 - n.len()..].copy_from_slice(n);
+        ptr = rest;
+    }
+    if !ptr.is_empty() {
+        return Err(Error::TrailingBytes(ptr.len()));
+    }
+    Ok(raw)
+}
+
+/// the inverse of [`ssh_ecdsa_to_raw`]
+fn raw_to_ssh_ecdsa(raw: &[u8]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(raw.len() + 10);
+    for half in raw.chunks(32) {
+        let n = match half.iter().position(|b| *b != 0) {
+            Some(i) => &half[i..],
+            None => &[],
+        };
+        let pad = n.first().is_some_and(|b| b & 0x80 != 0);
+        blob.extend_from_slice(&((n.len() + usize::from(pad)) as u32).to_be_bytes());
+        if pad {
+            blob.push(0);
+        }
+        blob.extend_from_slice(n);
+    }
+    blob
+}
 
-        match codec {
-            Codec::Ed25519Pub => {
-                let msg_encoding = msg_encoding.unwrap_or(Codec::Raw);
-                Ok((
-                    Self::EdDSA {
-                        version,
-                        msg_encoding,
-                        signature,
-                    },
-                    ptr,
+/// DER ECDSA signatures are a SEQUENCE of the INTEGERs r and s, whose
+/// contents are encoded just like the SSH mpints
+fn der_to_ssh_ecdsa(der: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::InvalidSignatureEncoding("invalid DER ECDSA signature".to_string());
+    // split off a tag and its short or one byte long form length
+    let tlv = |tag: u8, bytes: &[u8]| -> Option<(usize, usize)> {
+        match bytes {
+            [t, 0x81, len, ..] if *t == tag && *len >= 0x80 => Some((3, *len as usize)),
+            [t, len, ..] if *t == tag && *len < 0x80 => Some((2, *len as usize)),
+            _ => None,
+        }
+    };
+    let (hdr, len) = tlv(0x30, der).ok_or_else(invalid)?;
+    if der.len() != hdr + len {
+        return Err(invalid());
+    }
+    let mut blob = Vec::with_capacity(len + 4);
+    let mut ptr = &der[hdr..];
+    for _ in 0..2 {
+        let (hdr, len) = tlv(0x02, ptr).ok_or_else(invalid)?;
+        // a 256 bit integer with a leading zero byte is at most 33 bytes
+        if len == 0 || len > 33 || ptr.len() < hdr + len {
+            return Err(invalid());
+        }
+        blob.extend_from_slice(&(len as u32).to_be_bytes());
+        blob.extend_from_slice(&ptr[hdr..hdr + len]);
+        ptr = &ptr[hdr + len..];
+    }
+    if !ptr.is_empty() {
+        return Err(invalid());
+    }
+    Ok(blob)
+}
+
+impl TryFrom<Vec<u8>> for Varsig {
+    type Error = Error;
+
+    fn try_from(v: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(v.as_slice())
+    }
+}
+
+impl<'a> TryFrom<&'a Vec<u8>> for Varsig {
+    type Error = Error;
+
+    fn try_from(v: &'a Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(v.as_slice())
+    }
+}
+
+/// an EdDSA varsig over a raw payload
+#[cfg(feature = "ed25519-dalek")]
+impl From<ed25519_dalek::Signature> for Varsig {
+    fn from(sig: ed25519_dalek::Signature) -> Self {
+        Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes(sig.to_bytes().as_slice())
+            .build()
+    }
+}
+
+/// the signature of an EdDSA, Ed25519ctx or Ed25519ph varsig
+#[cfg(feature = "ed25519-dalek")]
+impl TryFrom<&Varsig> for ed25519_dalek::Signature {
+    type Error = Error;
+
+    fn try_from(vs: &Varsig) -> Result<Self, Self::Error> {
+        match vs {
+            Varsig::EdDSA { signature, .. }
+            | Varsig::Ed25519ctx { signature, .. }
+            | Varsig::Ed25519ph { signature, .. } => {
+                ed25519_dalek::Signature::from_slice(signature).map_err(|_| {
+                    Error::InvalidSignatureLength {
+                        expected: ed25519_dalek::SIGNATURE_LENGTH,
+                        actual: signature.len(),
+                    }
+                })
+            }
+            _ => Err(Error::UnsupportedAlgorithm(format!("{:?}", vs.codec()))),
+        }
+    }
+}
+
+/// an ES256K varsig over the sha2-256 hash of a raw payload with the raw
+/// r || s signature
+#[cfg(feature = "k256")]
+impl From<k256::ecdsa::Signature> for Varsig {
+    fn from(sig: k256::ecdsa::Signature) -> Self {
+        Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(&sig.to_bytes())
+            .build()
+    }
+}
+
+/// the ECDSA signature of a secp256k1 varsig, parsed as DER or r || s as its
+/// header records. a raw signature may be followed by a recovery id, which is
+/// left off, but by nothing else.
+#[cfg(feature = "k256")]
+impl TryFrom<&Varsig> for k256::ecdsa::Signature {
+    type Error = Error;
+
+    fn try_from(vs: &Varsig) -> Result<Self, Self::Error> {
+        let signature = match vs {
+            Varsig::Es256K { signature, .. }
+            | Varsig::Eip191 { signature, .. }
+            | Varsig::Eip712 { signature, .. } => signature,
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs.codec()))),
+        };
+        if vs.is_der() {
+            k256::ecdsa::Signature::from_der(signature)
+        } else {
+            let rs = raw_ecdsa(signature).ok_or_else(|| {
+                Error::InvalidSignatureEncoding(format!(
+                    "{} bytes is not r || s with an optional recovery id",
+                    signature.len()
                 ))
+            })?;
+            k256::ecdsa::Signature::from_slice(rs)
+        }
+        .map_err(|e| Error::InvalidSignatureEncoding(e.to_string()))
+    }
+}
+
+/// an ES256 varsig over the sha2-256 hash of a raw payload with the raw
+/// r || s signature
+#[cfg(feature = "p256")]
+impl From<p256::ecdsa::Signature> for Varsig {
+    fn from(sig: p256::ecdsa::Signature) -> Self {
+        Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(&sig.to_bytes())
+            .build()
+    }
+}
+
+/// the ECDSA signature of an ES256 varsig, parsed as DER or r || s as its
+/// header records
+#[cfg(feature = "p256")]
+impl TryFrom<&Varsig> for p256::ecdsa::Signature {
+    type Error = Error;
+
+    fn try_from(vs: &Varsig) -> Result<Self, Self::Error> {
+        match vs {
+            Varsig::Es256 {
+                der: true,
+                signature,
+                ..
+            } => p256::ecdsa::Signature::from_der(signature),
+            Varsig::Es256 { signature, .. } => p256::ecdsa::Signature::from_slice(signature),
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{:?}", vs.codec()))),
+        }
+        .map_err(|e| Error::InvalidSignatureEncoding(e.to_string()))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl TryFrom<bytes::Bytes> for Varsig {
+    type Error = Error;
+
+    fn try_from(b: bytes::Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(b.as_ref())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<'a> TryFrom<&'a bytes::Bytes> for Varsig {
+    type Error = Error;
+
+    fn try_from(b: &'a bytes::Bytes) -> Result<Self, Self::Error> {
+        Self::try_from(b.as_ref())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Varsig {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const CODECS: [Codec; 6] = [
+            Codec::Ed25519Pub,
+            Codec::Ed448Pub,
+            Codec::Secp256K1Pub,
+            Codec::Sr25519Pub,
+            Codec::P256Pub,
+            Codec::RsaPub,
+        ];
+        const ENCODINGS: [Codec; 6] = [
+            Codec::Identity,
+            Codec::Raw,
+            Codec::DagCbor,
+            Codec::DagJson,
+            Codec::Json,
+            Codec::Eip191,
+        ];
+        const FIRST_ATTRIBUTES: [u64; 5] = [IDENTITY, 0x12, 0x1b, SHA2_512, BIP340];
+        const SIGNATURE_LENS: [usize; 5] = [64, 65, 114, 256, 512];
+
+        // pick parts that are likely to type as one of the known variants,
+        // with the odd unknown or private use codec
+        let version = if u.arbitrary()? {
+            VarsigVersion::V1
+        } else {
+            VarsigVersion::V2
+        };
+        let code = match u.int_in_range(0..=7u8)? {
+            6 => u.int_in_range(*registry::PRIVATE_USE.start()..=*registry::PRIVATE_USE.end())?,
+            7 => u.arbitrary()?,
+            _ => u.choose(&CODECS)?.code(),
+        };
+        let msg_encoding = Some(*u.choose(&ENCODINGS)?);
+        let mut attributes = Vec::default();
+        if u.arbitrary()? {
+            attributes.push(*u.choose(&FIRST_ATTRIBUTES)?);
+            for _ in 0..u.int_in_range(0..=3u8)? {
+                attributes.push(u.arbitrary()?);
             }
-            _ => Ok((
-                Self::Unknown {
-                    version,
-                    codec,
-                    msg_encoding,
-                    attributes,
-                    signature,
-                },
-                ptr,
-            )),
         }
+        let len = if u.ratio(3, 4)? {
+            *u.choose(&SIGNATURE_LENS)?
+        } else {
+            u.arbitrary_len::<u8>()?
+        };
+        let signature = u.bytes(len)?.to_vec();
+
+        // only hand out varsigs the decoder produces, so they round trip
+        let v: Vec<u8> = CowVarsig {
+            version,
+            code,
+            msg_encoding,
+            encoding_segments: Vec::default(),
+            attributes,
+            signature: Cow::Owned(signature),
+        }
+        .into();
+        Self::try_from(v.as_slice()).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+impl<'a> TryDecodeFrom<'a> for Varsig {
+    type Error = Error;
+
+    fn try_decode_from(bytes: &'a [u8]) -> Result<(Self, &'a [u8]), Self::Error> {
+        let (cv, ptr) = CowVarsig::try_decode_from(bytes)?;
+        Ok((cv.into_owned(), ptr))
     }
 }
 
 /// Exposes direct access to the signature data
 impl AsRef<[u8]> for Varsig {
     fn as_ref(&self) -> &[u8] {
+        self.signature_bytes()
+    }
+}
+
+/// The header fields of a varsig, borrowed, so varsigs can be compared
+/// without encoding them. Fields a variant doesn't have are left empty.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct HeaderFields<'a> {
+    code: u64,
+    variant: u8,
+    version: u8,
+    msg_encoding: Option<u64>,
+    payload_encoding: Option<u64>,
+    encoding_segments: Segments<'a>,
+    hash: Option<u64>,
+    flags: [bool; 3],
+    context: &'a [u8],
+    param: Option<u64>,
+    salt_len: Option<usize>,
+    attributes: &'a [u64],
+}
+
+/// Payload encoding segments, ordered by their codec values
+#[derive(PartialEq, Eq)]
+struct Segments<'a>(&'a [Codec]);
+
+impl Ord for Segments<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .iter()
+            .map(|c| c.code())
+            .cmp(other.0.iter().map(|c| c.code()))
+    }
+}
+
+impl PartialOrd for Segments<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Varsig {
+    /// the untagged encoding that ordering and hashing go through
+    fn canonical_bytes(&self) -> Vec<u8> {
+        CowVarsig::from(self).into()
+    }
+
+    fn header_fields(&self) -> HeaderFields<'_> {
+        let mut fields = HeaderFields {
+            code: self.code(),
+            variant: 0,
+            version: u8::from(self.version()),
+            msg_encoding: self.header_msg_encoding().map(|c| c.code()),
+            payload_encoding: None,
+            encoding_segments: Segments(&[]),
+            hash: None,
+            flags: [false; 3],
+            context: &[],
+            param: None,
+            salt_len: None,
+            attributes: &[],
+        };
+        match self {
+            Varsig::Unknown {
+                encoding_segments,
+                attributes,
+                ..
+            } => {
+                fields.encoding_segments = Segments(encoding_segments);
+                fields.attributes = attributes;
+            }
+            Varsig::EdDSA { .. } => fields.variant = 1,
+            Varsig::Ed25519ctx { context, .. } => {
+                fields.variant = 2;
+                fields.context = context;
+            }
+            Varsig::Ed25519ph { context, .. } => {
+                fields.variant = 3;
+                fields.context = context;
+            }
+            Varsig::Ed448 { .. } => fields.variant = 4,
+            Varsig::Es256K {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            } => {
+                fields.variant = 5;
+                fields.hash = Some(hash.code());
+                fields.flags = [*low_s, *rfc6979, *der];
+            }
+            Varsig::Sr25519 { context, .. } => {
+                fields.variant = 6;
+                fields.context = context;
+            }
+            Varsig::Eip191 {
+                payload_encoding, ..
+            } => {
+                fields.variant = 7;
+                fields.payload_encoding = payload_encoding.map(|c| c.code());
+            }
+            Varsig::Eip712 {
+                domain_separator, ..
+            } => {
+                fields.variant = 8;
+                fields.context = domain_separator;
+            }
+            Varsig::Schnorr { aux, .. } => {
+                fields.variant = 9;
+                fields.param = *aux;
+            }
+            Varsig::Es256 {
+                hash,
+                low_s,
+                rfc6979,
+                der,
+                ..
+            } => {
+                fields.variant = 10;
+                fields.hash = Some(hash.code());
+                fields.flags = [*low_s, *rfc6979, *der];
+            }
+            Varsig::Rsa {
+                hash,
+                key_len,
+                padding,
+                ..
+            } => {
+                fields.variant = 11;
+                fields.hash = Some(hash.code());
+                fields.param = Some(*key_len as u64);
+                if let RsaPadding::Pss { salt_len } = padding {
+                    fields.salt_len = Some(*salt_len);
+                }
+            }
+            Varsig::PrivateUse {
+                encoding_segments,
+                attributes,
+                ..
+            } => {
+                fields.variant = 12;
+                fields.encoding_segments = Segments(encoding_segments);
+                fields.attributes = attributes;
+            }
+        }
+        fields
+    }
+}
+
+/// the headers are public so only the signature bytes are compared in
+/// constant time
+impl ConstantTimeEq for Varsig {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let header = Choice::from(u8::from(self.header_fields() == other.header_fields()));
+        header & self.signature_bytes().ct_eq(other.signature_bytes())
+    }
+}
+
+impl PartialEq for Varsig {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Varsig {}
+
+/// hashes the canonical encoding, which equal varsigs share
+impl Hash for Varsig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_bytes().hash(state);
+    }
+}
+
+/// orders varsigs by their canonical encoding, so a sorted set of varsigs is
+/// in the order of their encoded bytes. varsigs that differ but encode the
+/// same are then ordered by header field to stay consistent with equality.
+/// unlike equality this is not constant time.
+impl Ord for Varsig {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_bytes()
+            .cmp(&other.canonical_bytes())
+            .then_with(|| self.header_fields().cmp(&other.header_fields()))
+    }
+}
+
+impl PartialOrd for Varsig {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// scrubs the signature bytes. Varsig doesn't scrub itself on drop, since
+/// a `Drop` impl would stop callers moving fields out of it whenever the
+/// feature is enabled; wrap it in [`zeroize::Zeroizing`] for that.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Varsig {
+    fn zeroize(&mut self) {
         match self {
-            Varsig::Unknown { signature, .. } => signature.as_ref(),
-            Varsig::EdDSA { signature, .. } => signature.as_ref(),
+            Varsig::Unknown { signature, .. }
+            | Varsig::EdDSA { signature, .. }
+            | Varsig::Ed25519ctx { signature, .. }
+            | Varsig::Ed25519ph { signature, .. }
+            | Varsig::Ed448 { signature, .. }
+            | Varsig::Es256K { signature, .. }
+            | Varsig::Es256 { signature, .. }
+            | Varsig::Eip191 { signature, .. }
+            | Varsig::Eip712 { signature, .. }
+            | Varsig::Schnorr { signature, .. }
+            | Varsig::Sr25519 { signature, .. }
+            | Varsig::Rsa { signature, .. }
+            | Varsig::PrivateUse { signature, .. } => signature.zeroize(),
         }
     }
 }
 
 impl fmt::Debug for Varsig {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let (version, codec) = match self {
-            Varsig::Unknown { version, codec, .. } => (*version, *codec),
-            Varsig::EdDSA { version, .. } => (*version, Codec::Ed25519Pub),
-        };
-        write!(f, "{:?} v{} - {:?}", SIGIL, version, codec)
+        match self {
+            Varsig::PrivateUse { version, code, .. } => {
+                write!(f, "{:?} v{} - PrivateUse(0x{:x})", SIGIL, version, code)
+            }
+            _ => write!(f, "{:?} v{} - {:?}", SIGIL, self.version(), self.codec()),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Varsig {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Varsig v{=u8} - 0x{=u64:x} ({=usize} signature bytes)",
+            u8::from(self.version()),
+            self.code(),
+            self.as_ref().len()
+        )
     }
 }
 
 /// Builder for Varsigs
 #[derive(Clone, Debug, Default)]
 pub struct Builder {
-    version: u8,
+    version: VarsigVersion,
     codec: Codec,
     msg_encoding: Codec,
+    encoding_segments: Vec<Codec>,
     attributes: Vec<u64>,
     signature: Vec<u8>,
     encoding: Option<Base>,
+    private: Option<u64>,
 }
 
 impl Builder {
     /// create a new v1 varsig
     pub fn newv1(codec: Codec) -> Self {
         Self {
-            version: 1,
+            version: VarsigVersion::V1,
             codec,
             ..Default::default()
         }
@@ -272,33 +1755,74 @@ impl Builder {
     /// create a new v1 varsig
     pub fn newv2(codec: Codec) -> Self {
         Self {
-            version: 2,
+            version: VarsigVersion::V2,
             codec,
             ..Default::default()
         }
     }
 
-    /// create new v1 from ssh Signature
-    pub fn new_from_ssh_signature(sig: &Signature) -> Result<Self, Error> {
-        match sig.algorithm() {
-            Algorithm::Ed25519 => Ok(Self {
-                version: 2,
-                codec: Codec::Ed25519Pub,
-                msg_encoding: Codec::Raw,
-                signature: sig.as_bytes().to_vec(),
-                ..Default::default()
-            }),
-            _ => Err(Error::UnsupportedAlgorithm(sig.algorithm().to_string())),
+    /// create a new v1 varsig with a private use signing codec
+    pub fn newv1_private(code: u64) -> Self {
+        Self {
+            version: VarsigVersion::V1,
+            private: Some(code),
+            ..Default::default()
         }
     }
 
+    /// create a new v2 varsig with a private use signing codec
+    pub fn newv2_private(code: u64) -> Self {
+        Self {
+            version: VarsigVersion::V2,
+            private: Some(code),
+            ..Default::default()
+        }
+    }
+
+    /// create a new v2 varsig from an SSH signature. Ed25519, ECDSA over
+    /// NIST P-256 and RSA with SHA-256 or SHA-512 signatures are supported.
+    pub fn new_from_ssh_signature(sig: &Signature) -> Result<Self, Error> {
+        let (codec, attributes, signature) = match sig.algorithm() {
+            Algorithm::Ed25519 => (Codec::Ed25519Pub, Vec::default(), sig.as_bytes().to_vec()),
+            Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP256,
+            } => (
+                Codec::P256Pub,
+                vec![Codec::Sha2256.code()],
+                ssh_ecdsa_to_raw(sig.as_bytes())?,
+            ),
+            Algorithm::Rsa { hash: Some(hash) } => {
+                let hash = match hash {
+                    HashAlg::Sha256 => Codec::Sha2256,
+                    _ => Codec::Sha2512,
+                };
+                let signature = sig.as_bytes().to_vec();
+                (
+                    Codec::RsaPub,
+                    vec![hash.code(), signature.len() as u64],
+                    signature,
+                )
+            }
+            _ => return Err(Error::UnsupportedAlgorithm(sig.algorithm().to_string())),
+        };
+        Ok(Self {
+            version: VarsigVersion::V2,
+            codec,
+            msg_encoding: Codec::Raw,
+            attributes,
+            signature,
+            ..Default::default()
+        })
+    }
+
     /// set the key codec
     pub fn with_codec(mut self, codec: Codec) -> Self {
         self.codec = codec;
         self
     }
 
-    /// set the string encoding
+    /// set the string encoding, overriding the preferred encoding for the
+    /// signing codec
     pub fn with_encoding(mut self, base: Base) -> Self {
         self.encoding = Some(base);
         self
@@ -316,48 +1840,169 @@ impl Builder {
         self
     }
 
+    /// set the signature data from a string. the string may be hex (with or
+    /// without a leading "0x"), multibase encoded, or unprefixed base64 in
+    /// either the standard or url-safe alphabet, padded or not. they are
+    /// tried in that order.
+    pub fn with_signature_str(mut self, s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(Error::InvalidSignatureEncoding(s.to_string()));
+        }
+        self.signature = match decode_str(s) {
+            Ok(data) => data,
+            Err(_) => [
+                Base::Base64Pad,
+                Base::Base64,
+                Base::Base64UrlPad,
+                Base::Base64Url,
+            ]
+            .iter()
+            .find_map(|base| base.decode(s).ok())
+            .ok_or_else(|| Error::InvalidSignatureEncoding(s.to_string()))?,
+        };
+        Ok(self)
+    }
+
+    /// add a payload encoding segment after the payload encoding, e.g. the
+    /// encoding of the message an EIP-191 payload wraps. only v1 headers
+    /// have room for them.
+    pub fn with_encoding_segment(mut self, codec: Codec) -> Self {
+        self.encoding_segments.push(codec);
+        self
+    }
+
+    /// set the hash codec for algorithms that hash the payload before signing
+    pub fn with_hash_codec(mut self, codec: Codec) -> Self {
+        self.attributes = vec![codec.code()];
+        self
+    }
+
+    /// make a secp256k1 varsig an EIP-712 one over typed data with the given
+    /// domain separator
+    pub fn with_eip712_domain(mut self, domain_separator: &[u8; 32]) -> Self {
+        self.attributes = [Codec::Keccak256.code()]
+            .into_iter()
+            .chain(context_attributes(domain_separator))
+            .collect();
+        self
+    }
+
+    /// record an ES256K or ES256 signature as low-S normalized, after
+    /// setting the hash codec. the flag is experimental, see [`LOW_S`].
+    pub fn with_low_s(self) -> Self {
+        self.with_ecdsa_flag(LOW_S)
+    }
+
+    /// record an ES256K or ES256 signature as using a deterministic RFC 6979
+    /// nonce, after setting the hash codec. the flag is experimental, see
+    /// [`LOW_S`].
+    pub fn with_rfc6979(self) -> Self {
+        self.with_ecdsa_flag(RFC6979)
+    }
+
+    /// record an ES256K or ES256 signature as ASN.1 DER rather than the raw
+    /// r || s, after setting the hash codec. the flag is experimental, see
+    /// [`LOW_S`].
+    pub fn with_der(self) -> Self {
+        self.with_ecdsa_flag(DER)
+    }
+
+    fn with_ecdsa_flag(mut self, flag: u64) -> Self {
+        match self.attributes.as_mut_slice() {
+            [_] => self.attributes.push(flag),
+            [_, flags] => *flags |= flag,
+            _ => {}
+        }
+        self
+    }
+
+    /// mark a secp256k1 varsig as a BIP-340 Schnorr signature, optionally
+    /// recording an aux-data attribute. the [`BIP340`] marker is a private
+    /// use value, so only use this between parties that agree on it.
+    pub fn with_schnorr(mut self, aux: Option<u64>) -> Self {
+        self.attributes = [BIP340].into_iter().chain(aux).collect();
+        self
+    }
+
+    /// set the signing context for sr25519 signatures
+    pub fn with_signing_context(mut self, context: &[u8]) -> Self {
+        self.attributes = context_attributes(context).collect();
+        self
+    }
+
+    /// make an Ed25519 varsig an Ed25519ctx one bound to the signing context
+    pub fn with_ed25519_context(mut self, context: &[u8]) -> Self {
+        self.attributes = [IDENTITY]
+            .into_iter()
+            .chain(context_attributes(context))
+            .collect();
+        self
+    }
+
+    /// make an Ed25519 varsig an Ed25519ph one with the, possibly empty,
+    /// signing context
+    pub fn with_ed25519_prehash(mut self, context: &[u8]) -> Self {
+        self.attributes = [SHA2_512]
+            .into_iter()
+            .chain(context_attributes(context))
+            .collect();
+        self
+    }
+
+    /// set the hash codec and key length in bytes for RSA signatures
+    pub fn with_rsa_params(mut self, hash: Codec, key_len: usize) -> Self {
+        self.attributes = vec![hash.code(), key_len as u64];
+        self
+    }
+
+    /// set the padding scheme for RSA signatures, after the hash codec and
+    /// key length have been set with [`Builder::with_rsa_params`]
+    pub fn with_rsa_padding(mut self, padding: RsaPadding) -> Self {
+        self.attributes.truncate(2);
+        if let RsaPadding::Pss { salt_len } = padding {
+            self.attributes.push(salt_len as u64);
+        }
+        self
+    }
+
     /// set the signature-specific values for the header
     pub fn with_attributes(mut self, data: &Vec<u64>) -> Self {
         self.attributes = data.clone();
         self
     }
 
-    /// build it
+    /// build it without checking it; use [`Builder::try_build`] to reject
+    /// signature lengths and attributes that don't fit the signing algorithm
     pub fn build(&self) -> Varsig {
-        match self.codec {
-            Codec::Ed25519Pub => Varsig::EdDSA {
-                version: self.version,
-                msg_encoding: self.msg_encoding,
-                signature: self.signature.clone(),
-            },
-            _ => Varsig::Unknown {
-                version: self.version,
-                codec: self.codec,
-                msg_encoding: Some(self.msg_encoding),
-                attributes: self.attributes.clone(),
-                signature: self.signature.clone(),
-            },
+        self.parts().into_owned()
+    }
+
+    /// build it, checking it against the rules for its signing algorithm.
+    /// the parts are checked before they are typed so attributes the
+    /// algorithm has no room for are rejected rather than dropped.
+    pub fn try_build(&self) -> Result<Varsig, Error> {
+        let cv = self.parts();
+        cv.validate()?;
+        Ok(cv.into_owned())
+    }
+
+    fn parts(&self) -> CowVarsig<'_> {
+        CowVarsig {
+            version: self.version,
+            code: self.private.unwrap_or(self.codec.code()),
+            msg_encoding: Some(self.msg_encoding),
+            encoding_segments: self.encoding_segments.clone(),
+            attributes: self.attributes.clone(),
+            signature: Cow::Borrowed(&self.signature),
         }
     }
 
     /// build a base encoded varsig
     pub fn build_encoded(&self) -> EncodedVarsig {
-        let vs = match self.codec {
-            Codec::Ed25519Pub => Varsig::EdDSA {
-                version: self.version,
-                msg_encoding: self.msg_encoding,
-                signature: self.signature.clone(),
-            },
-            _ => Varsig::Unknown {
-                version: self.version,
-                codec: self.codec,
-                msg_encoding: Some(self.msg_encoding),
-                attributes: self.attributes.clone(),
-                signature: self.signature.clone(),
-            },
-        };
-        let base = self.encoding.unwrap_or(Base::Base16Lower);
-        BaseEncoded::new_base(base, vs)
+        let vs = self.build();
+        let base = self.encoding.unwrap_or_else(|| vs.encoding());
+        vs.into_encoded(base)
     }
 }
 
@@ -365,8 +2010,32 @@ impl Builder {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_borrowing_accessors() {
+        let vs = Builder::newv2(Codec::Ed448Pub)
+            .with_signature_bytes([3u8; 114].as_slice())
+            .build();
+        assert_eq!(vs.signature_bytes(), [3u8; 114].as_slice());
+        assert!(matches!(vs.attributes_slice(), Cow::Borrowed([])));
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_attributes(&[0x12, 0x80].to_vec())
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(vs.attributes_slice(), Cow::Borrowed([0x12, 0x80])));
+        assert_eq!(vs.attributes(), vs.attributes_slice().into_owned());
+
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert_eq!(vs.attributes_slice().as_ref(), [0x12]);
+        assert_eq!(vs.signature(), vs.signature_bytes().to_vec());
+    }
+
     #[test]
     fn test_encoded() {
+        let _lock = registry::lock_encodings();
         let vs = Builder::newv2(Codec::Ed25519Pub)
             .with_signature_bytes([0u8; 64].as_slice())
             .build_encoded();
@@ -375,12 +2044,129 @@ mod tests {
     }
 
     #[test]
-    fn test_default() {
-        let vs1 = Builder::newv2(Codec::default())
-            .with_msg_encoding(Codec::default())
-            .with_signature_bytes(Vec::default().as_slice())
-            .build_encoded();
-        let s = vs1.to_string();
+    fn test_try_build() {
+        let builder = Builder::newv2(Codec::Ed25519Pub).with_signature_bytes([0u8; 64].as_slice());
+        assert_eq!(builder.try_build().unwrap(), builder.build());
+
+        // the signature must be as long as the algorithm's
+        assert!(matches!(
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([0u8; 63].as_slice())
+                .try_build(),
+            Err(Error::InvalidSignatureLength {
+                expected: 64,
+                actual: 63
+            })
+        ));
+        assert!(matches!(
+            Builder::newv1(Codec::P256Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([0u8; 65].as_slice())
+                .try_build(),
+            Err(Error::InvalidSignatureLength { .. })
+        ));
+
+        // and carry the attributes the algorithm expects
+        assert!(matches!(
+            Builder::newv2(Codec::Ed448Pub)
+                .with_attributes(&[0x12].to_vec())
+                .with_signature_bytes([0u8; 114].as_slice())
+                .try_build(),
+            Err(Error::InvalidAttributes(_))
+        ));
+        assert!(matches!(
+            Builder::newv2(Codec::P256Pub)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .try_build(),
+            Err(Error::InvalidAttributes(_))
+        ));
+
+        // only v1 and v2 exist
+        assert!(matches!(
+            VarsigVersion::try_from(3),
+            Err(Error::InvalidVersion(3))
+        ));
+    }
+
+    #[test]
+    fn test_signature_str() {
+        let sig = [0xabu8; 64];
+        let hex = hex::encode(sig);
+        let strs = [
+            hex.clone(),
+            format!("0x{}", hex.to_uppercase()),
+            multibase::encode(Base::Base58Btc, sig),
+            multibase::encode(Base::Base64Url, sig),
+            Base::Base64Pad.encode(sig),
+            Base::Base64Url.encode(sig),
+        ];
+        for s in strs {
+            let vs = Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_str(&s)
+                .unwrap()
+                .build();
+            assert_eq!(vs.signature(), sig.to_vec());
+        }
+        for s in ["not a signature!", "", "  "] {
+            assert!(matches!(
+                Builder::newv2(Codec::Ed25519Pub).with_signature_str(s),
+                Err(Error::InvalidSignatureEncoding(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_retained_base() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        // the base it was encoded with is kept, not the preferred one
+        let e1 = vs.to_encoded(Base::Base58Btc);
+        assert_eq!(e1.base(), Base::Base58Btc);
+        let e2 = EncodedVarsig::try_from(e1.to_string().as_str()).unwrap();
+        assert_eq!(e2.base(), Base::Base58Btc);
+        assert_eq!(e1.to_string(), e2.to_string());
+        let e3 = e2.with_base(Base::Base32Lower);
+        assert_eq!(e3.base(), Base::Base32Lower);
+        assert!(e3.to_string().starts_with('b'));
+    }
+
+    #[test]
+    fn test_to_encoded() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let e1 = vs.to_encoded(Base::Base58Btc);
+        let e2 = vs.to_encoded(Base::Base32Lower);
+        assert!(e1.to_string().starts_with('z'));
+        assert!(e2.to_string().starts_with('b'));
+        let vs1 = EncodedVarsig::try_from(e1.to_string().as_str()).unwrap();
+        let vs2 = EncodedVarsig::try_from(e2.to_string().as_str()).unwrap();
+        assert_eq!(vs1.to_inner(), vs2.to_inner());
+    }
+
+    #[test]
+    fn test_preferred_encoding() {
+        let _lock = registry::lock_encodings();
+        let builder = Builder::newv2(Codec::Ed25519Pub).with_signature_bytes([0u8; 64].as_slice());
+        assert!(builder.build_encoded().to_string().starts_with('u'));
+        registry::set_preferred_encoding(Codec::Ed25519Pub.code(), Some(Base::Base16Lower));
+        assert!(builder.build_encoded().to_string().starts_with('f'));
+        registry::set_preferred_encoding(Codec::Ed25519Pub.code(), None);
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build_encoded();
+        assert!(vs.to_string().starts_with('f'));
+    }
+
+    #[test]
+    fn test_default() {
+        let _lock = registry::lock_encodings();
+        let vs1 = Builder::newv2(Codec::default())
+            .with_msg_encoding(Codec::default())
+            .with_signature_bytes(Vec::default().as_slice())
+            .build_encoded();
+        let s = vs1.to_string();
         let vs2 = EncodedVarsig::try_from(s.as_str()).unwrap();
         assert_eq!(vs1, vs2);
 
@@ -399,16 +2185,1070 @@ mod tests {
     }
 
     #[test]
-    fn test_eip191_unknown() {
-        // this builds a Varsig::Unknown since we don't know about EIP-191
-        // encoded data that is hashed with Keccak256 and signed with secp256k1
-        let vs1 = Builder::newv2(Codec::Secp256K1Pub)
+    fn test_owned_buffers() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(vs, Varsig::try_from(&v).unwrap());
+        assert_eq!(vs, Varsig::try_from(v).unwrap());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes_buffers() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        let b = bytes::Bytes::from(v);
+        assert_eq!(vs, Varsig::try_from(&b).unwrap());
+        assert_eq!(vs, Varsig::try_from(b).unwrap());
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        let vss = [
+            Builder::newv1(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes([0u8; 65].as_slice())
+                .build(),
+        ];
+        for vs in vss {
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs.encoded_len(), v.len());
+            if let Some(max) = Varsig::max_encoded_len(vs.codec()) {
+                assert!(v.len() <= max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_encoded_len() {
+        // the largest varsig of each kind, short of codecs that take up
+        // MAX_CODEC_LEN bytes
+        let context = [0xffu8; 255];
+        let cases = [
+            (
+                Builder::newv2(Codec::Ed25519Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_ed25519_prehash(&context)
+                    .with_signature_bytes([0u8; 64].as_slice()),
+                Varsig::ED25519CTX_MAX_ENCODED_LEN,
+            ),
+            (
+                Builder::newv2(Codec::Secp256K1Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_hash_codec(Codec::Sha3512)
+                    .with_der()
+                    .with_signature_bytes([0u8; 72].as_slice()),
+                Varsig::ES256K_MAX_ENCODED_LEN,
+            ),
+            (
+                Builder::newv2(Codec::P256Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_hash_codec(Codec::Sha2384)
+                    .with_der()
+                    .with_signature_bytes([0u8; 72].as_slice()),
+                Varsig::ES256_MAX_ENCODED_LEN,
+            ),
+            (
+                Builder::newv2(Codec::Secp256K1Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_eip712_domain(&[0xff; 32])
+                    .with_signature_bytes([0u8; 65].as_slice()),
+                Varsig::EIP712_MAX_ENCODED_LEN,
+            ),
+            (
+                Builder::newv2(Codec::Secp256K1Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_schnorr(Some(u64::MAX))
+                    .with_signature_bytes([0u8; 64].as_slice()),
+                Varsig::SCHNORR_MAX_ENCODED_LEN,
+            ),
+            (
+                Builder::newv2(Codec::RsaPub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_rsa_params(Codec::Sha2512, 512)
+                    .with_rsa_padding(RsaPadding::Pss {
+                        salt_len: usize::MAX,
+                    })
+                    .with_signature_bytes([0u8; 512].as_slice()),
+                Varsig::rsa_max_encoded_len(512),
+            ),
+            (
+                Builder::newv2(Codec::Sr25519Pub)
+                    .with_msg_encoding(Codec::DagJson)
+                    .with_signing_context(&context[..200])
+                    .with_signature_bytes([0u8; 64].as_slice()),
+                Varsig::sr25519_max_encoded_len(200),
+            ),
+        ];
+        for (builder, max) in cases {
+            let vs = builder.try_build().unwrap();
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs.encoded_len(), v.len());
+            assert!(v.len() <= max, "{:?}", vs);
+            // only the payload encoding, hash codec and aux data fall short
+            // of their maximum length
+            assert!(max - v.len() <= 3 * MAX_CODEC_LEN, "{:?}", vs);
+            if let Some(max) = Varsig::max_encoded_len(vs.codec()) {
+                assert!(v.len() <= max);
+            }
+        }
+
+        // signatures can't be longer than the bounds assume
+        assert!(matches!(
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([0u8; 66].as_slice())
+                .try_build(),
+            Err(Error::InvalidSignatureLength {
+                expected: 65,
+                actual: 66
+            })
+        ));
+        assert!(matches!(
+            Builder::newv2(Codec::P256Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_der()
+                .with_signature_bytes([0u8; 73].as_slice())
+                .try_build(),
+            Err(Error::InvalidSignatureLength { .. })
+        ));
+        assert!(matches!(
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_ed25519_context(&[0u8; 256])
+                .with_signature_bytes([0u8; 64].as_slice())
+                .try_build(),
+            Err(Error::InvalidAttributes(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_attributes() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
             .with_msg_encoding(Codec::Eip191)
-            .with_attributes(&[Codec::Keccak256.code()].to_vec())
+            .with_attributes(&[Codec::Raw.code()].to_vec())
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        let vs = vs.with_attribute(0, Codec::Keccak256.code()).unwrap();
+        assert_eq!(vs.attributes(), vec![Codec::Keccak256.code()]);
+        assert!(vs.clone().with_attribute(2, 0).is_err());
+
+        let mut vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(vs.set_attributes(vec![1]).is_err());
+        vs.set_msg_encoding(Codec::DagCbor).unwrap();
+        assert_eq!(vs.msg_encoding(), Codec::DagCbor);
+        assert!(vs.validate().is_ok());
+    }
+
+    #[test]
+    fn test_tagged() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
             .with_signature_bytes([0u8; 64].as_slice())
             .build();
+        let tagged = vs.to_tagged_bytes();
+        assert_eq!(tagged[0], 0x34);
+        assert_eq!(vs, Varsig::from_tagged_bytes(&tagged).unwrap());
+        // untagged decoding auto-detects the sigil
+        assert_eq!(vs, Varsig::try_from(tagged.as_slice()).unwrap());
+
+        let untagged: Vec<u8> = vs.into();
+        assert!(matches!(
+            Varsig::from_tagged_bytes(&untagged),
+            Err(Error::MissingSigil)
+        ));
+    }
+
+    fn golden_varsig(name: &str) -> Varsig {
+        let sig: Vec<u8> = (0u8..65).collect();
+        match name {
+            "eddsa-v1" => Builder::newv1(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::Raw)
+                .with_signature_bytes(&sig[..64]),
+            "eddsa-v2" => Builder::newv2(Codec::Ed25519Pub)
+                .with_msg_encoding(Codec::DagCbor)
+                .with_signature_bytes(&sig[..64]),
+            "secp256k1-eip191-v1" => Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes(&sig),
+            "secp256k1-eip191-dagcbor-v1" => Builder::newv1(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_encoding_segment(Codec::DagCbor)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes(&sig),
+            "secp256k1-eip191-v2" => Builder::newv2(Codec::Secp256K1Pub)
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes(&sig),
+            "private-use-v2" => Builder::newv2_private(0x300000)
+                .with_msg_encoding(Codec::Raw)
+                .with_attributes(&[1, 2, 3].to_vec())
+                .with_signature_bytes(&sig[..32]),
+            _ => panic!("unknown golden varsig {}", name),
+        }
+        .build()
+    }
+
+    #[test]
+    fn test_golden() {
+        let golden = include_str!("../examples/test_data/golden.txt");
+        let mut count = 0;
+        for line in golden.lines().filter(|l| !l.starts_with('#')) {
+            let mut parts = line.split_whitespace();
+            let (name, base, expected) = (
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+                parts.next().unwrap(),
+            );
+            let base = match base {
+                "base16lower" => Base::Base16Lower,
+                "base32lower" => Base::Base32Lower,
+                "base58btc" => Base::Base58Btc,
+                "base64url" => Base::Base64Url,
+                _ => panic!("unknown base {}", base),
+            };
+            let vs = golden_varsig(name);
+            assert_eq!(
+                vs.to_encoded(base).to_string(),
+                expected,
+                "{} {:?}",
+                name,
+                base
+            );
+            assert_eq!(
+                EncodedVarsig::try_from(expected).unwrap().to_inner(),
+                vs,
+                "{} {:?}",
+                name,
+                base
+            );
+            count += 1;
+        }
+        assert_eq!(count, 24);
+    }
+
+    #[test]
+    fn test_ed25519_flavors() {
+        let ctx = Builder::newv2(Codec::Ed25519Pub)
+            .with_ed25519_context(b"foo")
+            .with_signature_bytes([0u8; 64].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(&ctx, Varsig::Ed25519ctx { context, .. } if context == b"foo"));
+        let ph = Builder::newv2(Codec::Ed25519Pub)
+            .with_ed25519_prehash(b"")
+            .with_signature_bytes([0u8; 64].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(&ph, Varsig::Ed25519ph { context, .. } if context.is_empty()));
+        for vs in [ctx, ph] {
+            assert_eq!(vs.codec(), Codec::Ed25519Pub);
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+        }
+
+        // other attributes are kept rather than dropped
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_attributes(&[7].to_vec())
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(vs, Varsig::Unknown { .. }));
+        assert!(vs.validate().is_err());
+    }
+
+    #[test]
+    fn test_ed448() {
+        for vs in [
+            Builder::newv1(Codec::Ed448Pub),
+            Builder::newv2(Codec::Ed448Pub),
+        ] {
+            let vs = vs
+                .with_msg_encoding(Codec::Raw)
+                .with_signature_bytes([1u8; 114].as_slice())
+                .build();
+            assert!(matches!(vs, Varsig::Ed448 { .. }));
+            assert!(vs.encoded_len() <= Varsig::ED448_MAX_ENCODED_LEN);
+            let mut v: Vec<u8> = vs.clone().into();
+            // v1 finds the end of the signature from the layout
+            v.push(0xff);
+            let (vs2, rest) = Varsig::try_decode_from(v.as_slice()).unwrap();
+            assert_eq!(vs, vs2);
+            assert_eq!(rest.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_es256k() {
+        let vs1 = Builder::newv2(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes([0u8; 65].as_slice())
+            .build();
+        assert!(matches!(
+            vs1,
+            Varsig::Es256K {
+                hash: Codec::Keccak256,
+                ..
+            }
+        ));
+        let vs1v1 = Builder::newv1(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes([0u8; 65].as_slice())
+            .build();
+        for vs in [vs1.clone(), vs1v1] {
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+        }
+
+        // without a hash codec it stays unknown
+        let vs2 = Builder::newv2(Codec::Secp256K1Pub)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(vs2, Varsig::Unknown { .. }));
+    }
+
+    #[test]
+    fn test_sr25519() {
+        let vs1 = Builder::newv2(Codec::Sr25519Pub)
+            .with_signing_context(b"substrate")
+            .with_signature_bytes([0u8; 64].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(&vs1, Varsig::Sr25519 { context, .. } if context == b"substrate"));
+        let v: Vec<u8> = vs1.clone().into();
+        assert_eq!(vs1, Varsig::try_from(v.as_slice()).unwrap());
+
+        // a context that doesn't match its length stays unknown
+        let vs2 = Builder::newv2(Codec::Sr25519Pub)
+            .with_attributes(&[2, 0x61].to_vec())
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(vs2, Varsig::Unknown { .. }));
+        assert!(vs2.validate().is_err());
+    }
+
+    #[test]
+    fn test_schnorr() {
+        let vs1 = Builder::newv2(Codec::Secp256K1Pub)
+            .with_schnorr(Some(1))
+            .with_signature_bytes([0u8; 64].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(vs1, Varsig::Schnorr { aux: Some(1), .. }));
+        assert_eq!(vs1.attributes(), [BIP340, 1].to_vec());
+        assert!(registry::is_private_use(BIP340));
+        let vs1v1 = Builder::newv1(Codec::Secp256K1Pub)
+            .with_schnorr(None)
+            .with_signature_bytes([0u8; 64].as_slice())
+            .try_build()
+            .unwrap();
+        for vs in [vs1, vs1v1] {
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+        }
+
+        // schnorr signatures are 64 bytes, unlike recoverable ecdsa ones
+        assert!(Builder::newv2(Codec::Secp256K1Pub)
+            .with_schnorr(None)
+            .with_signature_bytes([0u8; 65].as_slice())
+            .try_build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_es256() {
+        let sha2_256 = Codec::try_from(0x12).unwrap();
+        for vs in [
+            Builder::newv2(Codec::P256Pub),
+            Builder::newv1(Codec::P256Pub),
+        ] {
+            let vs = vs
+                .with_msg_encoding(Codec::Raw)
+                .with_hash_codec(sha2_256)
+                .with_signature_bytes([0u8; 64].as_slice())
+                .build();
+            assert!(matches!(vs, Varsig::Es256 { .. }));
+            let v: Vec<u8> = vs.clone().into();
+            assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+        }
+
+        // r || s is always 64 bytes
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(sha2_256)
+            .with_signature_bytes([0u8; 72].as_slice())
+            .build();
+        let v: Vec<u8> = vs.into();
+        assert!(matches!(
+            Varsig::try_from(v.as_slice()),
+            Err(Error::InvalidSignatureLength {
+                expected: 64,
+                actual: 72
+            })
+        ));
+    }
+
+    #[test]
+    fn test_ecdsa_flags() {
+        let sha2_256 = Codec::try_from(0x12).unwrap();
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(sha2_256)
+            .with_rfc6979()
+            .with_low_s()
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            vs,
+            Varsig::Es256 {
+                low_s: true,
+                rfc6979: true,
+                ..
+            }
+        ));
+        assert_eq!(vs.attributes(), [0x12, LOW_S | RFC6979].to_vec());
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+
+        // unknown flags don't type as ES256K
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_attributes(&[0x12, 0x80].to_vec())
+            .with_signature_bytes([0u8; 64].as_slice())
+            .build();
+        assert!(matches!(vs, Varsig::Unknown { .. }));
+    }
+
+    #[test]
+    fn test_rsa() {
+        let sha2_256 = Codec::try_from(0x12).unwrap();
+        let vs1 = Builder::newv2(Codec::RsaPub)
+            .with_msg_encoding(Codec::Raw)
+            .with_rsa_params(sha2_256, 256)
+            .with_signature_bytes([0u8; 256].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(vs1, Varsig::Rsa { key_len: 256, .. }));
+        assert_eq!(vs1.attributes(), vec![0x12, 256]);
+        assert_eq!(vs1.padding(), Some(RsaPadding::Pkcs1v15));
         let v: Vec<u8> = vs1.clone().into();
+        assert_eq!(vs1, Varsig::try_from(v.as_slice()).unwrap());
+
+        let vs2 = Builder::newv2(Codec::RsaPub)
+            .with_rsa_params(sha2_256, 256)
+            .with_rsa_padding(RsaPadding::Pss { salt_len: 32 })
+            .with_signature_bytes([0u8; 256].as_slice())
+            .try_build()
+            .unwrap();
+        assert_eq!(vs2.padding(), Some(RsaPadding::Pss { salt_len: 32 }));
+        assert_eq!(vs2.attributes(), vec![0x12, 256, 32]);
+        let v: Vec<u8> = vs2.clone().into();
+        assert_eq!(vs2, Varsig::try_from(v.as_slice()).unwrap());
+
+        // the signature must match the key length
+        assert!(Builder::newv2(Codec::RsaPub)
+            .with_rsa_params(sha2_256, 512)
+            .with_signature_bytes([0u8; 256].as_slice())
+            .try_build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_eip712() {
+        let vs1 = Builder::newv2(Codec::Secp256K1Pub)
+            .with_eip712_domain(&[7u8; 32])
+            .with_signature_bytes([0u8; 65].as_slice())
+            .try_build()
+            .unwrap();
+        assert!(matches!(
+            &vs1,
+            Varsig::Eip712 { domain_separator, .. } if *domain_separator == [7u8; 32]
+        ));
+        assert_eq!(vs1.recovery_id(), Some(0));
+        let v: Vec<u8> = vs1.clone().into();
+        assert_eq!(vs1, Varsig::try_from(v.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_ssh_signature_roundtrip() {
+        let ed = Signature::new(Algorithm::Ed25519, [7u8; 64].to_vec()).unwrap();
+        let vs = Builder::new_from_ssh_signature(&ed).unwrap().build();
+        assert_eq!(vs.codec(), Codec::Ed25519Pub);
+        assert_eq!(Signature::try_from(&vs).unwrap(), ed);
+
+        // r with the high bit set and s with a leading zero byte
+        let mut raw = [0x81u8; 64];
+        raw[32] = 0;
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(&raw)
+            .build();
+        let sig = Signature::try_from(&vs).unwrap();
+        assert_eq!(
+            sig.algorithm(),
+            Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP256
+            }
+        );
+        assert_eq!(&sig.as_bytes()[..5], &[0, 0, 0, 33, 0]);
+        let back = Builder::new_from_ssh_signature(&sig).unwrap().build();
+        assert_eq!(back, vs);
+
+        // the same signature as DER converts to the same SSH signature
+        // rather than being split up as r || s
+        let mut der = vec![0x30, 0x45, 0x02, 0x21, 0x00];
+        der.extend_from_slice(&raw[..32]);
+        der.extend_from_slice(&[0x02, 0x20, 0x00]);
+        der.extend_from_slice(&raw[33..]);
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes(&der)
+            .build();
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes(&der[..der.len() - 1])
+            .build();
+        assert!(matches!(
+            Signature::try_from(&vs),
+            Err(Error::InvalidSignatureEncoding(_))
+        ));
+
+        let rsa = Signature::new(
+            Algorithm::Rsa {
+                hash: Some(HashAlg::Sha512),
+            },
+            [5u8; 256].to_vec(),
+        )
+        .unwrap();
+        let vs = Builder::new_from_ssh_signature(&rsa).unwrap().build();
+        assert_eq!(vs.hash_codec(), Some(Codec::Sha2512));
+        assert_eq!(vs.signature_bytes().len(), 256);
+        assert_eq!(Signature::try_from(vs).unwrap(), rsa);
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            Signature::try_from(&vs),
+            Err(Error::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let vs1 = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let mut sig = [1u8; 64];
+        sig[63] = 2;
+        let vs2 = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes(sig.as_slice())
+            .build();
+        assert!(bool::from(vs1.ct_eq(&vs1.clone())));
+        assert!(!bool::from(vs1.ct_eq(&vs2)));
+        assert_ne!(vs1, vs2);
+        // same signature, different header
+        let vs3 = Builder::newv1(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert_ne!(vs1, vs3);
+    }
+
+    #[test]
+    fn test_ecdsa_der() {
+        // a DER ES256 signature isn't held to the 64 byte r || s length
+        let der = [0x30u8; 71];
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes(der.as_slice())
+            .try_build()
+            .unwrap();
+        assert!(vs.is_der());
+        assert_eq!(vs.attributes(), [Codec::Sha2256.code(), DER].to_vec());
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(Varsig::try_from(v.as_slice()).unwrap(), vs);
+        // without the flag it must be r || s
+        assert!(Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes(der.as_slice())
+            .try_build()
+            .is_err());
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes([0x30u8; 65].as_slice())
+            .build();
+        assert!(matches!(vs, Varsig::Es256K { der: true, .. }));
+        // DER signatures carry no recovery id
+        assert_eq!(vs.recovery_id(), None);
+    }
+
+    #[cfg(feature = "k256")]
+    #[test]
+    fn test_k256_signature() {
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let sig: Signature = sk.sign(b"for great justice");
+        let vs = Varsig::from(sig);
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes(sig.to_der().as_bytes())
+            .build();
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+        let pk = sk.verifying_key().to_sec1_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+
+        // a recovery id is left off but any other trailing bytes are an error
+        for (v, ok) in [(&[1u8][..], true), (&[2], false), (&[27, 0], false)] {
+            let vs = Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes(&[&sig.to_bytes()[..], v].concat())
+                .build();
+            match Signature::try_from(&vs) {
+                Ok(s) => assert!(ok && s == sig),
+                Err(e) => assert!(!ok && matches!(e, Error::InvalidSignatureEncoding(_))),
+            }
+        }
+    }
+
+    #[cfg(feature = "p256")]
+    #[test]
+    fn test_p256_signature() {
+        use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+        let sk = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let sig: Signature = sk.sign(b"for great justice");
+        let vs = Varsig::from(sig);
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+
+        let vs = Builder::newv2(Codec::P256Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_der()
+            .with_signature_bytes(sig.to_der().as_bytes())
+            .build();
+        assert_eq!(Signature::try_from(&vs).unwrap(), sig);
+        let pk = sk.verifying_key().to_sec1_bytes();
+        assert!(vs.verify(&pk, b"for great justice").is_ok());
+    }
+
+    #[test]
+    fn test_hash_ord() {
+        use std::collections::{BTreeSet, HashSet};
+        let varsigs = [
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([2u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            Builder::newv1(Codec::Ed25519Pub)
+                .with_signature_bytes([3u8; 64].as_slice())
+                .build(),
+            Builder::newv2(Codec::Secp256K1Pub)
+                .with_hash_codec(Codec::Sha2256)
+                .with_signature_bytes([4u8; 64].as_slice())
+                .build(),
+        ];
+        let set: HashSet<Varsig> = varsigs.iter().chain(varsigs.iter()).cloned().collect();
+        assert_eq!(set.len(), 4);
+        // sorted by encoded bytes: the v1 header first, then secp256k1's
+        // smaller codec, then by signature
+        let sorted: Vec<Varsig> = varsigs
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            sorted,
+            [
+                varsigs[2].clone(),
+                varsigs[3].clone(),
+                varsigs[1].clone(),
+                varsigs[0].clone()
+            ]
+        );
+        let mut encoded: Vec<Vec<u8>> = varsigs.iter().map(|vs| vs.clone().into()).collect();
+        encoded.sort();
+        let sorted: Vec<Vec<u8>> = sorted.into_iter().map(|vs| vs.into()).collect();
+        assert_eq!(sorted, encoded);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+        let mut vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        vs.zeroize();
+        assert!(vs.signature_bytes().is_empty());
+        assert_eq!(vs.codec(), Codec::Ed25519Pub);
+
+        // fields can still be moved out with the feature enabled
+        let vs = zeroize::Zeroizing::new(vs);
+        let Varsig::EdDSA { signature, .. } = (*vs).clone() else {
+            panic!("expected an EdDSA varsig");
+        };
+        assert!(signature.is_empty());
+    }
+
+    #[test]
+    fn test_parts() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Raw)
+            .with_hash_codec(Codec::Sha2256)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let mut parts = vs.clone().into_parts();
+        assert_eq!(parts.version, VarsigVersion::V2);
+        assert_eq!(parts.code, Codec::Secp256K1Pub.code());
+        assert_eq!(parts.msg_encoding, Some(Codec::Raw));
+        assert_eq!(parts.attributes, vec![Codec::Sha2256.code()]);
+        assert_eq!(vs, Varsig::from_parts(parts.clone()).unwrap());
+
+        // swap the hash without rebuilding the varsig
+        parts.attributes[0] = Codec::Keccak256.code();
+        let vs2 = Varsig::from_parts(parts.clone()).unwrap();
+        assert_eq!(vs2.hash_codec(), Some(Codec::Keccak256));
+
+        // an attribute after the hash that isn't a set of ecdsa flags
+        parts.attributes.push(0x80);
+        assert!(matches!(
+            Varsig::from_parts(parts),
+            Err(Error::InvalidAttributes(_))
+        ));
+
+        let mut parts = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build()
+            .into_parts();
+        parts.signature.pop();
+        assert!(matches!(
+            Varsig::from_parts(parts),
+            Err(Error::InvalidSignatureLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let hex: String = vs
+            .to_tagged_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        for s in [
+            vs.to_encoded(Base::Base58Btc).to_string(),
+            vs.to_encoded(Base::Base32Lower).to_string(),
+            vs.to_encoded(Base::Base16Lower).to_string(),
+            hex.clone(),
+            hex.to_uppercase(),
+            format!("0x{}", hex),
+        ] {
+            assert_eq!(vs, s.parse::<Varsig>().unwrap());
+        }
+        // the encoded form parses too
+        let s = vs.to_encoded(Base::Base58Btc).to_string();
+        assert_eq!(vs, EncodedVarsig::try_from(s.as_str()).unwrap().to_inner());
+
+        assert!(matches!(
+            format!("{}00", hex).parse::<Varsig>(),
+            Err(Error::TrailingBytes(1))
+        ));
+        assert!("not a varsig".parse::<Varsig>().is_err());
+    }
+
+    #[test]
+    fn test_typed_attributes() {
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Sha2256)
+            .with_low_s()
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert_eq!(
+            vs.typed_attributes(),
+            Attributes::Ecdsa {
+                hash: Codec::Sha2256,
+                low_s: true,
+                rfc6979: false,
+                der: false
+            }
+        );
+        assert_eq!(vs.hash_codec(), Some(Codec::Sha2256));
+
+        let vs = Builder::newv2(Codec::RsaPub)
+            .with_rsa_params(Codec::Sha2512, 256)
+            .with_signature_bytes([1u8; 256].as_slice())
+            .build();
+        assert_eq!(
+            vs.typed_attributes(),
+            Attributes::Rsa {
+                hash: Codec::Sha2512,
+                key_len: 256,
+                padding: RsaPadding::Pkcs1v15
+            }
+        );
+        assert_eq!(vs.hash_codec(), Some(Codec::Sha2512));
+
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert_eq!(vs.typed_attributes(), Attributes::None);
+        assert_eq!(vs.hash_codec(), None);
+
+        let vs = Builder::newv2_private(0x300001)
+            .with_attributes(&vec![7, 8])
+            .with_signature_bytes([1u8; 8].as_slice())
+            .build();
+        assert_eq!(vs.typed_attributes(), Attributes::Custom(vec![7, 8]));
+        assert_eq!(vs.hash_codec(), None);
+        // Codec has no private use values
+        assert_eq!(vs.code(), 0x300001);
+        assert_eq!(vs.codec(), Codec::Identity);
+    }
+
+    #[test]
+    fn test_from_bytes_canonical() {
+        for vs in [
+            Builder::newv2(Codec::Ed25519Pub)
+                .with_signature_bytes([1u8; 64].as_slice())
+                .build(),
+            // codecs without a layout are fine as long as they round trip
+            Varsig::try_from([0x01, 0xeb, 0x01, 0x12, 0x71, 0xaa].as_slice()).unwrap(),
+        ] {
+            let untagged: Vec<u8> = vs.clone().into();
+            assert_eq!(vs, Varsig::from_bytes_canonical(&untagged).unwrap());
+            let tagged = vs.to_tagged_bytes();
+            assert_eq!(vs, Varsig::from_bytes_canonical(&tagged).unwrap());
+            // decode(encode(x)) == x and encode(decode(b)) == b
+            let v: Vec<u8> = Varsig::from_bytes_canonical(&untagged).unwrap().into();
+            assert_eq!(v, untagged);
+
+            let mut trailing = tagged.clone();
+            trailing.push(0);
+            if vs.signature_bytes().len() == 64 {
+                assert!(matches!(
+                    Varsig::from_bytes_canonical(&trailing),
+                    Err(Error::TrailingBytes(1))
+                ));
+            }
+        }
+        // overlong varuints don't decode at all
+        assert!(Varsig::from_bytes_canonical(&[0x02, 0xed, 0x81, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_strict() {
+        let vs = Builder::newv2(Codec::Ed25519Pub)
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        let mut v: Vec<u8> = vs.clone().into();
+        assert_eq!(vs, Varsig::from_bytes_strict(&v).unwrap());
+        let tagged = vs.to_tagged_bytes();
+        assert_eq!(vs, Varsig::from_bytes_strict(&tagged).unwrap());
+
+        // trailing bytes
+        v.push(0);
+        assert!(Varsig::try_from(v.as_slice()).is_ok());
+        assert!(matches!(
+            Varsig::from_bytes_strict(&v),
+            Err(Error::TrailingBytes(1))
+        ));
+
+        // a non-minimal varuint for the ed25519 codec
+        let mut v: Vec<u8> = vs.into();
+        v.splice(1..3, [0xed, 0x81, 0x00]);
+        assert!(Varsig::from_bytes_strict(&v).is_err());
+
+        // bls12-381 g1 has no layout
+        let v: Vec<u8> = Builder::newv2(Codec::try_from(0xea).unwrap())
+            .with_signature_bytes([1u8; 96].as_slice())
+            .build()
+            .into();
+        assert!(Varsig::try_from(v.as_slice()).is_ok());
+        assert!(matches!(
+            Varsig::from_bytes_strict(&v),
+            Err(Error::UnknownCodec(0xea))
+        ));
+
+        // attributes es256k doesn't have
+        let v: Vec<u8> = Builder::newv2(Codec::Secp256K1Pub)
+            .with_attributes(&[0x12, 0x80, 0x80].to_vec())
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build()
+            .into();
+        assert!(matches!(
+            Varsig::from_bytes_strict(&v),
+            Err(Error::InvalidAttributes(_))
+        ));
+    }
+
+    #[test]
+    fn test_version_conversion() {
+        for name in ["eddsa-v1", "secp256k1-eip191-v1"] {
+            let v1 = golden_varsig(name);
+            let v2 = v1.upgrade_to_v2().unwrap();
+            assert_eq!(v2.version(), VarsigVersion::V2);
+            assert_eq!(v2.msg_encoding(), v1.msg_encoding());
+            assert_eq!(v2.as_ref(), v1.as_ref());
+            assert_eq!(v2.downgrade_to_v1().unwrap(), v1);
+        }
+
+        // v2 has no room for encoding segments
+        assert!(matches!(
+            golden_varsig("secp256k1-eip191-dagcbor-v1").upgrade_to_v2(),
+            Err(Error::InvalidEncodingInfo(_))
+        ));
+
+        // and v1 none for aux data or flags
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_schnorr(Some(1))
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(vs.downgrade_to_v1().is_err());
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_hash_codec(Codec::Keccak256)
+            .with_low_s()
+            .with_signature_bytes([1u8; 64].as_slice())
+            .build();
+        assert!(matches!(
+            vs.downgrade_to_v1(),
+            Err(Error::InvalidAttributes(_))
+        ));
+    }
+
+    #[test]
+    fn test_v1_field_order() {
+        // after the version, the v1 header is the key codec, the
+        // signature-specific fields, the payload encoding segments and then
+        // the raw signature, the order README.md diagrams
+        let vs = Builder::newv1(Codec::RsaPub)
+            .with_msg_encoding(Codec::DagCbor)
+            .with_attributes(&[0x12, 4].to_vec())
+            .with_signature_bytes([0xaa; 4].as_slice())
+            .build();
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(
+            v,
+            [0x01, 0x85, 0x24, 0x12, 0x04, 0x71, 0xaa, 0xaa, 0xaa, 0xaa].to_vec()
+        );
+        assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_encoding_segments() {
+        let sig: Vec<u8> = (0u8..65).collect();
+        // secp256k1 + keccak256 + eip191 wrapping dag-cbor
+        let vs = Builder::newv1(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_encoding_segment(Codec::DagCbor)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes(&sig)
+            .build();
+        assert!(matches!(
+            vs,
+            Varsig::Eip191 {
+                payload_encoding: Some(Codec::DagCbor),
+                ..
+            }
+        ));
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(v[..7], [0x01, 0xe7, 0x01, 0x1b, 0x91, 0xa3, 0x03]);
+        assert_eq!(v[7], 0x71);
+        assert_eq!(v.len(), vs.encoded_len());
         let vs2 = Varsig::try_from(v.as_slice()).unwrap();
-        assert_eq!(vs1, vs2);
+        assert_eq!(vs, vs2);
+        let v2: Vec<u8> = vs2.into();
+        assert_eq!(v, v2);
+
+        // other algorithms keep their segments untyped
+        let vs = Builder::newv1(Codec::Ed25519Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_encoding_segment(Codec::DagJson)
+            .with_signature_bytes(&sig[..64])
+            .build();
+        assert!(matches!(vs, Varsig::Unknown { .. }));
+        let v: Vec<u8> = vs.clone().into();
+        assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+
+        // v2 headers have no room for them
+        let vs = Builder::newv2(Codec::Secp256K1Pub)
+            .with_msg_encoding(Codec::Eip191)
+            .with_encoding_segment(Codec::DagCbor)
+            .with_hash_codec(Codec::Keccak256)
+            .with_signature_bytes(&sig)
+            .build();
+        assert!(matches!(vs.validate(), Err(Error::InvalidEncodingInfo(_))));
+    }
+
+    #[test]
+    fn test_eip191_roundtrip() {
+        // EIP-191 encoded data that is hashed with Keccak256 and signed with
+        // secp256k1 is a typed EIP-191 varsig
+        let mut sig = [0u8; 65];
+        sig[64] = 28;
+        for vs1 in [
+            Builder::newv1(Codec::Secp256K1Pub),
+            Builder::newv2(Codec::Secp256K1Pub),
+        ] {
+            let vs1 = vs1
+                .with_msg_encoding(Codec::Eip191)
+                .with_attributes(&[Codec::Keccak256.code()].to_vec())
+                .with_signature_bytes(sig.as_slice())
+                .build();
+            assert!(matches!(vs1, Varsig::Eip191 { .. }));
+            assert_eq!(vs1.msg_encoding(), Codec::Eip191);
+            assert_eq!(vs1.recovery_id(), Some(1));
+            let v: Vec<u8> = vs1.clone().into();
+            let vs2 = Varsig::try_from(v.as_slice()).unwrap();
+            assert_eq!(vs1, vs2);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod arbitrary_tests {
+    use super::*;
+    use arbitrary::{Arbitrary, Unstructured};
+
+    #[test]
+    fn test_arbitrary_roundtrip() {
+        let data: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&data);
+        let mut count = 0;
+        while !u.is_empty() {
+            if let Ok(vs) = Varsig::arbitrary(&mut u) {
+                let v: Vec<u8> = vs.clone().into();
+                assert_eq!(vs, Varsig::try_from(v.as_slice()).unwrap());
+                count += 1;
+            }
+        }
+        assert!(count > 0);
     }
 }